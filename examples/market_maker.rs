@@ -0,0 +1,66 @@
+// A bare-bones spread capture market maker: every second, move a standing bid and ask around
+// the mid, and pull the side that would push inventory past the limit.
+//
+// Configure with SF_ACCOUNT, SF_VENUE (default TESTEX), SF_STOCK (default FOOBAR), plus
+// STOCKFIGHTERAPI as usual.
+//
+//     cargo run --example market_maker
+
+extern crate market;
+
+use market::quotepair::{QuotePair, Target};
+use market::scheduler::Scheduler;
+use market::{Qty, StockfighterClient};
+
+use std::env;
+use std::thread;
+use std::time::Duration;
+
+static MAX_INVENTORY: i32 = 500;
+static WIDTH: i32 = 20;
+
+fn env_or( name: &str, default: &str ) -> String {
+    env::var( name ).unwrap_or( default.to_owned() )
+}
+
+fn main() {
+    let account = env_or( "SF_ACCOUNT", "EXB123456" );
+    let venue = env_or( "SF_VENUE", "TESTEX" );
+    let stock = env_or( "SF_STOCK", "FOOBAR" );
+
+    let client = StockfighterClient::new();
+    let mut pair = QuotePair::new( account, venue.clone(), stock.clone() );
+    let mut inventory: i32 = 0;
+    let mut cash: i64 = 0;
+    let mut scheduler = Scheduler::new();
+    scheduler.every( "requote", Duration::from_secs( 1 ) );
+
+    loop {
+        let mut requote = false;
+        scheduler.tick( |tag| if tag == "requote" { requote = true } );
+        if requote {
+            if let Ok( quote ) = client.quote( &venue, &stock ) {
+                if quote.bid > 0 && quote.ask > 0 {
+                    let mid = ( quote.bid + quote.ask ) / 2;
+                    let size = Qty( 50 );
+                    // Fills a failed sync saw still count against the limit
+                    let exposure = inventory + pair.pending_fills().iter()
+                                                   .map( |fill| fill.fill.qty.signed( fill.direction == "buy" ) )
+                                                   .sum::<i32>();
+                    pair.set_bid( if exposure < MAX_INVENTORY { Some( Target { price: mid - WIDTH / 2, qty: size } ) } else { None } );
+                    pair.set_ask( if exposure > -MAX_INVENTORY { Some( Target { price: mid + WIDTH / 2, qty: size } ) } else { None } );
+                }
+                match pair.sync() {
+                    Ok( fills ) => for fill in fills {
+                        let signed = fill.fill.qty.signed( fill.direction == "buy" );
+                        inventory += signed;
+                        cash -= signed as i64 * fill.fill.price as i64;
+                    },
+                    Err( e ) => println!("sync failed: {}", e ),
+                }
+                println!("bid {} ask {} | inventory {} cash {}", quote.bid, quote.ask, inventory, cash );
+            }
+        }
+        thread::sleep( Duration::from_millis( 50 ) );
+    }
+}
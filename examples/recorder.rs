@@ -0,0 +1,81 @@
+// Records every quote that moves the touch, and every new last trade, as one JSON
+// market::event::MarketEvent per line -- market::recording::replay reads it back. Writes to
+// stdout, or to the file named by SF_OUT (gzipped if it ends in .gz and the `gzip` feature is
+// on). With SF_INSTANCE set instead, it writes events.jsonl in that GM instance's data
+// directory (see market::datadir).
+//
+// Configure with SF_VENUE (default TESTEX) and SF_STOCK (default FOOBAR).
+//
+//     cargo run --example recorder > events.jsonl
+//     SF_OUT=events.jsonl.gz cargo run --features gzip --example recorder
+//
+// A gzipped file killed mid-run is missing its trailer; everything up to the last flushed
+// block still decompresses.
+
+extern crate market;
+extern crate serde_json;
+
+use market::datadir::DataDir;
+use market::event::MarketEvent;
+use market::filter::QuoteFilter;
+use market::StockfighterClient;
+
+use std::env;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+fn env_or( name: &str, default: &str ) -> String {
+    env::var( name ).unwrap_or( default.to_owned() )
+}
+
+fn write_event( out: &mut Write, event: &MarketEvent ) -> io::Result<()> {
+    match serde_json::to_string( event ) {
+        Ok( line ) => writeln!( out, "{}", line ),
+        Err( e ) => Err( io::Error::new( io::ErrorKind::Other, e.to_string() ) ),
+    }
+}
+
+fn main() {
+    let venue = env_or( "SF_VENUE", "TESTEX" );
+    let stock = env_or( "SF_STOCK", "FOOBAR" );
+
+    let mut out: Box<Write> = match env::var( "SF_OUT" ) {
+        Ok( path ) => market::recording::create( Path::new( &path ) ).unwrap(),
+        Err( _ ) => match env::var( "SF_INSTANCE" ) {
+            Ok( instance ) => DataDir::new( &instance ).recording( "events.jsonl" ).unwrap(),
+            Err( _ ) => Box::new( io::stdout() ),
+        },
+    };
+
+    let client = StockfighterClient::new();
+    let mut filter = QuoteFilter::new().on_touch_change();
+    let mut last_trade = String::new();
+    loop {
+        match client.quote( &venue, &stock ) {
+            Ok( quote ) => {
+                let mut events = vec![];
+                if quote.last_trade != last_trade {
+                    last_trade = quote.last_trade.clone();
+                    events.extend( MarketEvent::trade_from( &quote ) );
+                }
+                if filter.accept( &quote ) {
+                    events.push( MarketEvent::Quote( quote ) );
+                }
+                for event in &events {
+                    if let Err( e ) = write_event( &mut *out, event ) {
+                        let _ = writeln!( io::stderr(), "write failed: {}", e );
+                        return;
+                    }
+                }
+            },
+            Err( e ) => {
+                let _ = writeln!( io::stderr(), "quote failed: {}", e );
+                thread::sleep( Duration::from_secs( 1 ) );
+            },
+        }
+        thread::sleep( Duration::from_millis( 100 ) );
+    }
+}
@@ -0,0 +1,59 @@
+// Buys a block of shares at the touch until the position is reached.
+//
+// Configure with SF_ACCOUNT, SF_VENUE (default TESTEX), SF_STOCK (default FOOBAR) and
+// SF_QTY (default 100), plus STOCKFIGHTERAPI as usual.
+//
+//     cargo run --example simple_buyer
+
+extern crate market;
+
+use market::blotter::Blotter;
+use market::intent::{ExecutionPolicy, Intent, TouchPolicy};
+use market::{Qty, StockfighterClient};
+
+use std::env;
+use std::thread;
+use std::time::Duration;
+
+fn env_or( name: &str, default: &str ) -> String {
+    env::var( name ).unwrap_or( default.to_owned() )
+}
+
+fn main() {
+    let account = env_or( "SF_ACCOUNT", "EXB123456" );
+    let venue = env_or( "SF_VENUE", "TESTEX" );
+    let stock = env_or( "SF_STOCK", "FOOBAR" );
+    let target: i32 = env_or( "SF_QTY", "100" ).parse().unwrap_or( 100 );
+
+    let client = StockfighterClient::new();
+    let mut blotter = Blotter::new();
+    let mut policy = TouchPolicy::new( Qty( 50 ) );
+    let intent = Intent::TargetPosition { venue: venue.clone(), stock: stock.clone(), target: target };
+
+    loop {
+        if let Err( e ) = blotter.settle( "buyer" ) {
+            println!("cancel failed: {}", e );
+        }
+        if blotter.position( "buyer" ) >= target {
+            break;
+        }
+        let quote = match client.quote( &venue, &stock ) {
+            Ok( quote ) => quote,
+            Err( e ) => {
+                println!("quote failed: {}", e );
+                thread::sleep( Duration::from_secs( 1 ) );
+                continue;
+            },
+        };
+        for order in policy.orders( &intent, &account, &quote, blotter.position( "buyer" ) ) {
+            match blotter.submit( "buyer", &order ) {
+                Ok( response ) => println!("order {}: filled {} of {}", response.id,
+                                           response.total_filled, response.original_qty ),
+                Err( e ) => println!("order failed: {}", e ),
+            }
+        }
+        thread::sleep( Duration::from_millis( 500 ) );
+    }
+    println!("done: long {} {}, cash {} cents", blotter.position( "buyer" ), stock,
+             blotter.cash( "buyer" ) );
+}
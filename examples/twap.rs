@@ -0,0 +1,77 @@
+// Works a buy order evenly over time (a TWAP): the target position is raised a slice at a
+// time on a timer, and each slice is worked passively until its deadline. Every couple of
+// seconds the working order is cancelled and replaced at the current touch.
+//
+// Configure with SF_ACCOUNT, SF_VENUE (default TESTEX), SF_STOCK (default FOOBAR), SF_QTY
+// (default 1000) and SF_MINUTES (default 5), plus STOCKFIGHTERAPI as usual.
+//
+//     cargo run --example twap
+
+extern crate market;
+
+use market::blotter::Blotter;
+use market::intent::{ExecutionPolicy, Intent, TouchPolicy};
+use market::scheduler::Scheduler;
+use market::{Qty, StockfighterClient};
+
+use std::env;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+static SLICES: i32 = 10;
+
+fn env_or( name: &str, default: &str ) -> String {
+    env::var( name ).unwrap_or( default.to_owned() )
+}
+
+fn main() {
+    let account = env_or( "SF_ACCOUNT", "EXB123456" );
+    let venue = env_or( "SF_VENUE", "TESTEX" );
+    let stock = env_or( "SF_STOCK", "FOOBAR" );
+    let total: i32 = env_or( "SF_QTY", "1000" ).parse().unwrap_or( 1000 );
+    let minutes: u64 = env_or( "SF_MINUTES", "5" ).parse().unwrap_or( 5 );
+
+    let slice_time = Duration::from_secs( minutes * 60 / SLICES as u64 );
+    let client = StockfighterClient::new();
+    let mut blotter = Blotter::new();
+    let mut policy = TouchPolicy::new( Qty( ( total / SLICES ).max( 1 ) as u32 ) );
+    let mut scheduler = Scheduler::new();
+    scheduler.every( "slice", slice_time );
+    scheduler.every( "work", Duration::from_secs( 2 ) );
+
+    let mut slices_released = 1;
+    let mut intent = Intent::TargetPositionBy { venue: venue.clone(), stock: stock.clone(),
+                                                target: total / SLICES,
+                                                deadline: SystemTime::now() + slice_time };
+
+    while blotter.position( "twap" ) < total {
+        let mut tags = vec![];
+        scheduler.tick( |tag| tags.push( tag.to_owned() ) );
+        for tag in tags {
+            if tag == "slice" && slices_released < SLICES {
+                slices_released += 1;
+                intent = Intent::TargetPositionBy { venue: venue.clone(), stock: stock.clone(),
+                                                    target: total * slices_released / SLICES,
+                                                    deadline: SystemTime::now() + slice_time };
+            }
+            if tag == "work" {
+                if let Err( e ) = blotter.settle( "twap" ) {
+                    println!("cancel failed: {}", e );
+                }
+                if let Ok( quote ) = client.quote( &venue, &stock ) {
+                    for order in policy.orders( &intent, &account, &quote, blotter.position( "twap" ) ) {
+                        if let Err( e ) = blotter.submit( "twap", &order ) {
+                            println!("order failed: {}", e );
+                        }
+                    }
+                }
+                println!("{} / {} bought", blotter.position( "twap" ), total );
+            }
+        }
+        thread::sleep( Duration::from_millis( 100 ) );
+    }
+    if let Err( e ) = blotter.settle( "twap" ) {
+        println!("cancel failed: {}", e );
+    }
+    println!("done: {} bought", blotter.position( "twap" ) );
+}
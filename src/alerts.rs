@@ -0,0 +1,281 @@
+// Alert rules.
+//
+// Most failed levels fail quietly: the position creeps past the limit, the spread blows out
+// and the bot keeps quoting into it, or the quote feed stalls and everything after that is
+// decided on stale data. Alerts watch for that sort of thing from rules written the way you'd
+// say them:
+//
+//     position > 500
+//     spread > 50 for 10s
+//     last < $20.00
+//     no quotes for 5s
+//
+// Feed the alerts every quote and position change, and call `tick` from the strategy loop so
+// the time-based rules notice when nothing is happening. A rule fires once when its condition
+// has held for its duration, and re-arms once the condition clears.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use {Quote, StockfighterErr};
+use clock::{duration_millis, Clock, SystemClock};
+use price::parse_price;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Ask minus bid, in cents (only while both sides are quoted)
+    Spread,
+    Bid,
+    Ask,
+    Last,
+    Position,
+    /// Special: true when no quote has arrived for the rule's duration
+    NoQuotes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+    Equal,
+    NotEqual,
+}
+
+impl Op {
+    fn holds( &self, value: i64, threshold: i64 ) -> bool {
+        match *self {
+            Op::Above => value > threshold,
+            Op::AtLeast => value >= threshold,
+            Op::Below => value < threshold,
+            Op::AtMost => value <= threshold,
+            Op::Equal => value == threshold,
+            Op::NotEqual => value != threshold,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// The rule as written
+    pub text: String,
+    pub metric: Metric,
+    pub op: Op,
+    pub threshold: i64,
+    /// How long the condition has to hold before the rule fires
+    pub sustain: Duration,
+}
+
+fn bad_rule( text: &str, why: &str ) -> StockfighterErr {
+    StockfighterErr::BadRule( format!("{}: {}", text, why) )
+}
+
+fn millis( duration: Duration ) -> i64 {
+    duration_millis( duration ).min( ::std::i64::MAX as u64 ) as i64
+}
+
+// "500ms", "10s", "2m"
+fn parse_duration( text: &str ) -> Option<Duration> {
+    let ( number, unit_ms ) = if text.ends_with( "ms" ) {
+        ( &text[ ..text.len() - 2 ], 1 )
+    } else if text.ends_with( 's' ) {
+        ( &text[ ..text.len() - 1 ], 1000 )
+    } else if text.ends_with( 'm' ) {
+        ( &text[ ..text.len() - 1 ], 60000 )
+    } else {
+        return None;
+    };
+    // Huge numbers are a bad rule, not an overflow
+    number.parse::<u64>().ok()
+          .and_then( |n| n.checked_mul( unit_ms ) )
+          .map( Duration::from_millis )
+}
+
+impl Rule {
+    /// Parses a rule: `<metric> <op> <value> [for <duration>]`, or `no quotes for <duration>`.
+    /// Metrics are spread, bid, ask, last and position; ops are > >= < <= == !=. Prices can be
+    /// given in cents ("50") or dollars ("$0.50", "0.50"); durations as 500ms, 10s or 2m.
+    ///
+    /// # Example
+    /// ```
+    /// use market::alerts::{Metric, Rule};
+    /// let rule = Rule::parse( "spread > $0.50 for 10s" ).unwrap();
+    /// assert_eq!( rule.metric, Metric::Spread );
+    /// assert_eq!( rule.threshold, 50 );
+    /// assert!( Rule::parse( "spread is wide" ).is_err() );
+    /// ```
+    pub fn parse( text: &str ) -> Result<Rule, StockfighterErr> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.len() == 4 && words[ 0 ] == "no" && words[ 1 ] == "quotes" && words[ 2 ] == "for" {
+            let sustain = try!( parse_duration( words[ 3 ] ).ok_or_else( || bad_rule( text, "bad duration" ) ) );
+            return Ok( Rule {
+                text: text.to_owned(),
+                metric: Metric::NoQuotes,
+                op: Op::Above,
+                threshold: 0,
+                sustain: sustain,
+            });
+        }
+        if words.len() != 3 && !( words.len() == 5 && words[ 3 ] == "for" ) {
+            return Err( bad_rule( text, "expected `<metric> <op> <value> [for <duration>]`" ) );
+        }
+        let metric = match words[ 0 ] {
+            "spread" => Metric::Spread,
+            "bid" => Metric::Bid,
+            "ask" => Metric::Ask,
+            "last" => Metric::Last,
+            "position" => Metric::Position,
+            _ => return Err( bad_rule( text, "unknown metric" ) ),
+        };
+        let op = match words[ 1 ] {
+            ">" => Op::Above,
+            ">=" => Op::AtLeast,
+            "<" => Op::Below,
+            "<=" => Op::AtMost,
+            "==" => Op::Equal,
+            "!=" => Op::NotEqual,
+            _ => return Err( bad_rule( text, "unknown comparison" ) ),
+        };
+        let value = words[ 2 ];
+        let threshold = if metric != Metric::Position && ( value.contains( '.' ) || value.starts_with( '$' ) ) {
+            try!( parse_price( value ) ) as i64
+        } else {
+            try!( value.parse::<i64>().map_err( |_| bad_rule( text, "bad value" ) ) )
+        };
+        let sustain = if words.len() == 5 {
+            try!( parse_duration( words[ 4 ] ).ok_or_else( || bad_rule( text, "bad duration" ) ) )
+        } else {
+            Duration::from_secs( 0 )
+        };
+        Ok( Rule { text: text.to_owned(), metric: metric, op: op, threshold: threshold, sustain: sustain } )
+    }
+}
+
+struct Armed {
+    rule: Rule,
+    // When the condition started holding
+    since: Option<Instant>,
+    fired: bool,
+}
+
+pub struct Alerts {
+    rules: Vec<Armed>,
+    quote: Option<Quote>,
+    last_quote_at: Instant,
+    position: i64,
+    on_alert: Option<Box<FnMut( &Rule, i64 )>>,
+    clock: Arc<Clock>,
+}
+
+impl Alerts {
+    pub fn new() -> Alerts {
+        Alerts::with_clock( Arc::new( SystemClock ) )
+    }
+
+    /// Alerts that time conditions with `clock` rather than the system clock.
+    pub fn with_clock( clock: Arc<Clock> ) -> Alerts {
+        Alerts {
+            rules: vec![],
+            quote: None,
+            last_quote_at: clock.now(),
+            position: 0,
+            on_alert: None,
+            clock: clock,
+        }
+    }
+
+    /// Adds a rule in the syntax `Rule::parse` takes.
+    pub fn rule( mut self, text: &str ) -> Result<Alerts, StockfighterErr> {
+        let rule = try!( Rule::parse( text ) );
+        self.rules.push( Armed { rule: rule, since: None, fired: false } );
+        Ok( self )
+    }
+
+    /// Called with the rule and the value that tripped it (for `no quotes`, milliseconds
+    /// since the last quote).
+    ///
+    /// # Example
+    /// ```no_run
+    /// let mut alerts = market::alerts::Alerts::new()
+    ///                      .rule( "position > 500" ).unwrap()
+    ///                      .rule( "spread > 50 for 10s" ).unwrap()
+    ///                      .rule( "no quotes for 5s" ).unwrap()
+    ///                      .on_alert( |rule, value| println!("\x07ALERT {} (at {})", rule.text, value ) );
+    /// let client = market::StockfighterClient::new();
+    /// loop {
+    ///   if let Ok( quote ) = client.quote( "TESTEX", "FOOBAR" ) {
+    ///     alerts.quote( &quote );
+    ///   }
+    ///   alerts.tick();
+    /// }
+    /// ```
+    pub fn on_alert<F: FnMut( &Rule, i64 ) + 'static>( mut self, callback: F ) -> Alerts {
+        self.on_alert = Some( Box::new( callback ) );
+        self
+    }
+
+    /// Takes a new quote and evaluates the rules.
+    pub fn quote( &mut self, quote: &Quote ) {
+        self.last_quote_at = self.clock.now();
+        self.quote = Some( quote.clone() );
+        self.tick();
+    }
+
+    /// Takes the current net position and evaluates the rules.
+    pub fn position( &mut self, position: i32 ) {
+        self.position = position as i64;
+        self.tick();
+    }
+
+    fn value( &self, metric: Metric, now: Instant ) -> Option<i64> {
+        let quote = self.quote.as_ref();
+        let positive = |price: i32| if price > 0 { Some( price as i64 ) } else { None };
+        match metric {
+            Metric::Spread => quote.and_then( |quote| {
+                if quote.bid > 0 && quote.ask > 0 { Some( ( quote.ask - quote.bid ) as i64 ) } else { None }
+            }),
+            Metric::Bid => quote.and_then( |quote| positive( quote.bid ) ),
+            Metric::Ask => quote.and_then( |quote| positive( quote.ask ) ),
+            Metric::Last => quote.and_then( |quote| positive( quote.last ) ),
+            Metric::Position => Some( self.position ),
+            Metric::NoQuotes => Some( millis( now - self.last_quote_at ) ),
+        }
+    }
+
+    /// Evaluates every rule against the latest values. Returns the rules that fired.
+    pub fn tick( &mut self ) -> Vec<Rule> {
+        let now = self.clock.now();
+        let mut fired = vec![];
+        for index in 0..self.rules.len() {
+            let value = self.value( self.rules[ index ].rule.metric, now );
+            let armed = &mut self.rules[ index ];
+            let holds = match ( armed.rule.metric, value ) {
+                // The duration is the whole condition: "no quotes for 5s"
+                ( Metric::NoQuotes, Some( quiet ) ) => quiet >= millis( armed.rule.sustain ),
+                ( _, Some( value ) ) => armed.rule.op.holds( value, armed.rule.threshold ),
+                ( _, None ) => false,
+            };
+            if !holds {
+                armed.since = None;
+                armed.fired = false;
+                continue;
+            }
+            if armed.since.is_none() {
+                armed.since = Some( now );
+            }
+            let since = armed.since.unwrap_or( now );
+            let sustained = armed.rule.metric == Metric::NoQuotes || now - since >= armed.rule.sustain;
+            if sustained && !armed.fired {
+                armed.fired = true;
+                fired.push( ( armed.rule.clone(), value.unwrap_or( 0 ) ) );
+            }
+        }
+        if let Some( ref mut on_alert ) = self.on_alert {
+            for &( ref rule, value ) in &fired {
+                on_alert( rule, value );
+            }
+        }
+        fired.into_iter().map( |( rule, _ )| rule ).collect()
+    }
+}
@@ -0,0 +1,184 @@
+// Catching obviously bogus quotes before a strategy trades on them.
+//
+// Now and then a quote comes through that can't be right: the bid miles above the ask, a
+// price that moved half its value in one tick, sizes of zero on a side that has a price.
+// AnomalyDetector checks each quote against a few such rules. Depending on the mode, an
+// anomalous quote is either held back from the strategy entirely (quarantined) or passed
+// through marked as suspect; either way the anomaly callback hears about it.
+
+use std::collections::HashMap;
+
+use {Qty, Quote};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// Bid above ask by more than the allowed number of cents
+    Crossed { bid: i32, ask: i32 },
+    /// A price moved by more than the allowed percentage since the last good quote
+    Jump { field: &'static str, from: i32, to: i32, percent: f64 },
+    /// A side has a price but no size
+    BadSize { field: &'static str, size: Qty },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Anomalous quotes are not delivered at all
+    Quarantine,
+    /// Anomalous quotes are delivered, flagged as suspect
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Clean,
+    Suspect( Vec<Anomaly> ),
+    Quarantined( Vec<Anomaly> ),
+}
+
+impl Verdict {
+    /// Whether the quote should be handed on to the strategy.
+    pub fn deliver( &self ) -> bool {
+        match *self {
+            Verdict::Quarantined( _ ) => false,
+            _ => true,
+        }
+    }
+}
+
+pub struct AnomalyDetector {
+    mode: Mode,
+    max_cross: i32,
+    max_jump_percent: f64,
+    // (bid, ask, last) of the last quote that passed, per symbol
+    previous: HashMap<String, (i32, i32, i32)>,
+    // Quotes in a row that jumped from `previous` but agree with each other: the latest
+    // (bid, ask, last) and how many so far, per symbol
+    moved: HashMap<String, ((i32, i32, i32), usize)>,
+    rebaseline_after: usize,
+    on_anomaly: Option<Box<FnMut( &Quote, &[Anomaly] )>>,
+}
+
+impl AnomalyDetector {
+    /// Defaults: any crossed quote is anomalous, as is a 20% move in one tick.
+    ///
+    /// # Example
+    /// ```
+    /// use market::anomaly::{AnomalyDetector, Mode};
+    /// let mut detector = AnomalyDetector::new( Mode::Quarantine )
+    ///                        .max_jump_percent( 10.0 )
+    ///                        .on_anomaly( |quote, anomalies| {
+    ///                            println!("quarantined {}: {:?}", quote.symbol, anomalies );
+    ///                        });
+    /// let client = market::StockfighterClient::new();
+    /// let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    /// if detector.check( &quote ).deliver() {
+    ///   println!("good quote: {} / {}", quote.bid, quote.ask );
+    /// }
+    /// ```
+    pub fn new( mode: Mode ) -> AnomalyDetector {
+        AnomalyDetector {
+            mode: mode,
+            max_cross: 0,
+            max_jump_percent: 20.0,
+            previous: HashMap::new(),
+            moved: HashMap::new(),
+            rebaseline_after: 3,
+            on_anomaly: None,
+        }
+    }
+
+    /// How far (in cents) the bid may sit above the ask before it counts as bogus.
+    pub fn max_cross( mut self, cents: i32 ) -> AnomalyDetector {
+        self.max_cross = cents;
+        self
+    }
+
+    pub fn max_jump_percent( mut self, percent: f64 ) -> AnomalyDetector {
+        self.max_jump_percent = percent;
+        self
+    }
+
+    /// After this many quotes in a row that jump from the reference but agree with each other,
+    /// the market really has moved: the latest becomes the new reference and is passed as
+    /// clean. Defaults to 3.
+    pub fn rebaseline_after( mut self, quotes: usize ) -> AnomalyDetector {
+        self.rebaseline_after = quotes.max( 1 );
+        self
+    }
+
+    pub fn on_anomaly<F: FnMut( &Quote, &[Anomaly] ) + 'static>( mut self, callback: F ) -> AnomalyDetector {
+        self.on_anomaly = Some( Box::new( callback ) );
+        self
+    }
+
+    /// The anomalies in `quote`, without updating any state.
+    pub fn anomalies( &self, quote: &Quote ) -> Vec<Anomaly> {
+        let mut found = vec![];
+        if quote.bid > 0 && quote.ask > 0 && quote.bid - quote.ask > self.max_cross {
+            found.push( Anomaly::Crossed { bid: quote.bid, ask: quote.ask } );
+        }
+        if quote.bid > 0 && quote.bid_size.is_zero() {
+            found.push( Anomaly::BadSize { field: "bidSize", size: quote.bid_size } );
+        }
+        if quote.ask > 0 && quote.ask_size.is_zero() {
+            found.push( Anomaly::BadSize { field: "askSize", size: quote.ask_size } );
+        }
+        if let Some( &reference ) = self.previous.get( &quote.symbol ) {
+            found.extend( self.jumps( reference, quote ) );
+        }
+        found
+    }
+
+    fn jumps( &self, ( bid, ask, last ): ( i32, i32, i32 ), quote: &Quote ) -> Vec<Anomaly> {
+        let mut found = vec![];
+        let prices = [ ( "bid", bid, quote.bid ), ( "ask", ask, quote.ask ), ( "last", last, quote.last ) ];
+        for &( field, from, to ) in &prices {
+            if from <= 0 || to <= 0 {
+                continue;
+            }
+            let percent = ( to - from ).abs() as f64 * 100.0 / from as f64;
+            if percent > self.max_jump_percent {
+                found.push( Anomaly::Jump { field: field, from: from, to: to, percent: percent } );
+            }
+        }
+        found
+    }
+
+    /// Checks `quote`, calling the anomaly callback if anything is wrong. Only clean quotes
+    /// become the reference later jumps are measured from, so one bad tick can't drag the
+    /// baseline along with it -- but a run of `rebaseline_after` jumped quotes that agree with
+    /// each other moves the reference to them, so a genuine move doesn't quarantine the symbol
+    /// for good.
+    pub fn check( &mut self, quote: &Quote ) -> Verdict {
+        let found = self.anomalies( quote );
+        let prices = ( quote.bid, quote.ask, quote.last );
+        if found.is_empty() {
+            self.previous.insert( quote.symbol.clone(), prices );
+            self.moved.remove( &quote.symbol );
+            return Verdict::Clean;
+        }
+        let only_jumps = found.iter().all( |anomaly| match *anomaly {
+            Anomaly::Jump { .. } => true,
+            _ => false,
+        });
+        if only_jumps {
+            let agreeing = match self.moved.get( &quote.symbol ) {
+                Some( &( latest, count ) ) if self.jumps( latest, quote ).is_empty() => count + 1,
+                _ => 1,
+            };
+            if agreeing >= self.rebaseline_after {
+                self.previous.insert( quote.symbol.clone(), prices );
+                self.moved.remove( &quote.symbol );
+                return Verdict::Clean;
+            }
+            self.moved.insert( quote.symbol.clone(), ( prices, agreeing ) );
+        }
+        if let Some( ref mut callback ) = self.on_anomaly {
+            callback( quote, &found );
+        }
+        match self.mode {
+            Mode::Quarantine => Verdict::Quarantined( found ),
+            Mode::Tag => Verdict::Suspect( found ),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// How requests authenticate.
+//
+// The official server reads the key from `X-Starfighter-Authorization`, but it has also
+// accepted `X-Stockfighter-Authorization`, the GM UI uses a cookie, and community
+// reimplementations of the server each pick their own. Auth covers those cases so the same
+// client code can talk to any of them.
+//
+// On a shared machine an environment variable is visible to anyone who can list processes.
+// `key_file` reads the key from a file instead, and (on Unix) refuses one that other users
+// can read, the way ssh treats private keys. With the `keychain` feature, `keychain` reads it
+// from the OS keychain (the macOS login keychain, or the Secret Service on Linux) so it's
+// never on disk in the clear at all. That goes through each platform's own command line tool
+// rather than a binding, so the feature adds no dependencies.
+
+use hyper::header::Headers;
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+#[cfg(feature = "keychain")]
+use std::process::Command;
+
+use {get_apikey, StockfighterErr};
+
+#[cfg(unix)]
+fn check_private( file: &File, path: &Path ) -> Result<(), StockfighterErr> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = try!( file.metadata() ).permissions().mode();
+    if mode & 0o077 != 0 {
+        let message = format!("{} is readable by other users (mode {:o}); chmod 600 it", path.display(), mode & 0o777 );
+        return Err( StockfighterErr::IO( io::Error::new( io::ErrorKind::PermissionDenied, message ) ) );
+    }
+    Ok( () )
+}
+
+#[cfg(not(unix))]
+fn check_private( _: &File, _: &Path ) -> Result<(), StockfighterErr> {
+    Ok( () )
+}
+
+/// How to authenticate. Its Debug output names the scheme and header but never the key.
+///
+/// # Example
+/// ```
+/// let auth = market::Auth::starfighter( "0123456789abcdef".to_owned() );
+/// let shown = format!("{:?}", auth );
+/// assert!( shown.contains( "X-Starfighter-Authorization" ) );
+/// assert!( !shown.contains( "0123456789abcdef" ) );
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// `X-Starfighter-Authorization`, with the key looked up by `get_apikey` when the request
+    /// is sent. This is what everything uses unless told otherwise.
+    Env,
+    /// The key in an arbitrary header
+    Header { name: String, key: String },
+    /// The key in a cookie
+    Cookie { name: String, key: String },
+}
+
+// Written out by hand so the key never ends up in a log: an Auth shows which scheme and header
+// it uses, never the key itself
+impl fmt::Debug for Auth {
+    fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+        match *self {
+            Auth::Env => write!(f, "Env"),
+            Auth::Header { ref name, .. } => write!(f, "Header {{ name: {:?}, key: <redacted> }}", name),
+            Auth::Cookie { ref name, .. } => write!(f, "Cookie {{ name: {:?}, key: <redacted> }}", name),
+        }
+    }
+}
+
+#[cfg(all(feature = "keychain", target_os = "macos"))]
+fn keychain_command( service: &str, account: &str ) -> Command {
+    let mut command = Command::new( "security" );
+    command.args( &[ "find-generic-password", "-s", service, "-a", account, "-w" ] );
+    command
+}
+
+#[cfg(all(feature = "keychain", not(target_os = "macos")))]
+fn keychain_command( service: &str, account: &str ) -> Command {
+    // Anywhere without secret-tool, spawning it fails with NotFound, which is the error we want
+    let mut command = Command::new( "secret-tool" );
+    command.args( &[ "lookup", "service", service, "account", account ] );
+    command
+}
+
+impl Default for Auth {
+    fn default() -> Auth {
+        Auth::Env
+    }
+}
+
+impl Auth {
+    pub fn starfighter( key: String ) -> Auth {
+        Auth::Header { name: "X-Starfighter-Authorization".to_owned(), key: key }
+    }
+
+    pub fn stockfighter( key: String ) -> Auth {
+        Auth::Header { name: "X-Stockfighter-Authorization".to_owned(), key: key }
+    }
+
+    /// `X-Starfighter-Authorization`, with the key read from the first line of the file at
+    /// `path`. On Unix the file must not be readable by group or others.
+    ///
+    /// # Example
+    /// ```
+    /// use std::path::Path;
+    /// let auth = market::Auth::key_file( Path::new( "/home/me/.stockfighter/key" ) ).unwrap();
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// order.process_order_with( &auth ).unwrap();
+    /// ```
+    pub fn key_file( path: &Path ) -> Result<Auth, StockfighterErr> {
+        let mut file = try!( File::open( path ) );
+        try!( check_private( &file, path ) );
+        let mut contents = String::new();
+        try!( file.read_to_string( &mut contents ) );
+        let key = contents.lines().next().unwrap_or( "" ).trim().to_owned();
+        if key.is_empty() {
+            return Err( StockfighterErr::NoApiKey );
+        }
+        Ok( Auth::starfighter( key ) )
+    }
+
+    /// `X-Starfighter-Authorization`, with the key read from the OS keychain entry for
+    /// `service` and `account` -- `security` on macOS, `secret-tool` (libsecret) on Linux.
+    /// No entry is `NoApiKey`; a missing tool or an unsupported OS is an IO error.
+    ///
+    /// Store the key first with
+    /// `security add-generic-password -s stockfighter -a me -w` or
+    /// `secret-tool store --label=stockfighter service stockfighter account me`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// let auth = market::Auth::keychain( "stockfighter", "me" ).unwrap();
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// order.process_order_with( &auth ).unwrap();
+    /// ```
+    #[cfg(feature = "keychain")]
+    pub fn keychain( service: &str, account: &str ) -> Result<Auth, StockfighterErr> {
+        let output = try!( keychain_command( service, account ).output() );
+        if !output.status.success() {
+            return Err( StockfighterErr::NoApiKey );
+        }
+        let key = String::from_utf8_lossy( &output.stdout ).trim().to_owned();
+        if key.is_empty() {
+            return Err( StockfighterErr::NoApiKey );
+        }
+        Ok( Auth::starfighter( key ) )
+    }
+
+    /// The `api_key` cookie the GM UI authenticates with.
+    pub fn cookie( key: String ) -> Auth {
+        Auth::Cookie { name: "api_key".to_owned(), key: key }
+    }
+
+    /// Adds whatever this scheme needs to `headers`.
+    pub fn apply( &self, headers: &mut Headers ) -> Result<(), StockfighterErr> {
+        match *self {
+            Auth::Env => {
+                let key = try!( get_apikey() );
+                headers.set_raw( "X-Starfighter-Authorization", vec![ key.into_bytes() ] );
+            },
+            Auth::Header { ref name, ref key } => {
+                headers.set_raw( name.clone(), vec![ key.as_bytes().to_vec() ] );
+            },
+            Auth::Cookie { ref name, ref key } => {
+                headers.set_raw( "Cookie", vec![ format!("{}={}", name, key).into_bytes() ] );
+            },
+        }
+        Ok( () )
+    }
+}
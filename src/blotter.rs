@@ -0,0 +1,340 @@
+// Client side record of every order sent, tagged with the strategy that sent it.
+//
+// The API has no free-text field on orders, so when several strategies share one account there
+// is no way to tell from the server's side whose fill is whose. The blotter keeps that mapping
+// (order id -> tag) locally, alongside the latest response seen for each order, which is enough
+// to attribute fills, positions and cash flow back to a strategy.
+//
+// Every order sent through `submit` also keeps the exact JSON body that went out, with its
+// checksum, so "what did my bot actually send?" can be answered byte for byte afterwards.
+// That goes for orders the venue rejected or that never arrived too: every attempt lands in
+// a send log, numbered in the order it was made, whether or not it came back with an id.
+//
+// The same fill can turn up more than once -- in the response to a status poll and again on
+// the executions stream, say. Fills are therefore ingested idempotently, keyed on
+// (order id, ts, qty, price), so positions and cash never count one twice.
+//
+// `to_json` writes the whole blotter out for notebooks and dashboards, in a versioned schema
+// that `from_json` reads back.
+
+use serde_json;
+
+use std::collections::BTreeMap;
+
+use {Auth, Order, OrderFill, OrderResponse, StockfighterErr};
+use recording::checksum;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlotterEntry {
+    pub tag: String,
+    pub order: OrderResponse,
+    /// The exact request body sent, empty for orders recorded rather than submitted
+    #[serde(default)]
+    pub payload: String,
+    /// recording::checksum of `payload`
+    #[serde(default)]
+    pub payload_checksum: String,
+}
+
+impl BlotterEntry {
+    /// True if `payload` still matches the checksum taken when it was sent. Always false for
+    /// entries with no payload.
+    pub fn payload_intact( &self ) -> bool {
+        checksum( self.payload.as_bytes() ) == self.payload_checksum
+    }
+}
+
+/// One attempt to send an order through `submit`, whether or not it got through.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendAttempt {
+    /// Counts up from 1, in the order the attempts were made
+    pub attempt: u64,
+    pub tag: String,
+    /// The exact request body sent
+    pub payload: String,
+    /// recording::checksum of `payload`
+    pub payload_checksum: String,
+    /// The id the venue gave the order, if it accepted it
+    #[serde(default)]
+    pub order_id: Option<i32>,
+    /// Why the send failed, if it did
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl SendAttempt {
+    /// True if `payload` still matches the checksum taken when it was sent.
+    pub fn payload_intact( &self ) -> bool {
+        checksum( self.payload.as_bytes() ) == self.payload_checksum
+    }
+}
+
+fn same_fill( a: &OrderFill, b: &OrderFill ) -> bool {
+    a.ts == b.ts && a.qty == b.qty && a.price == b.price
+}
+
+// Appends the fills from `incoming` that aren't in `fills` yet, returning how many were new
+fn merge_fills( fills: &mut Vec<OrderFill>, incoming: Vec<OrderFill> ) -> usize {
+    let mut added = 0;
+    for fill in incoming {
+        if !fills.iter().any( |known| same_fill( known, &fill ) ) {
+            fills.push( fill );
+            added += 1;
+        }
+    }
+    added
+}
+
+fn net_position( entries: Vec<&BlotterEntry> ) -> i32 {
+    entries.iter().map( |entry| {
+        let buy = entry.order.direction == "buy";
+        entry.order.fills.iter().map( |fill| fill.qty.signed( buy ) ).sum::<i32>()
+    }).sum()
+}
+
+fn net_cash( entries: Vec<&BlotterEntry> ) -> i64 {
+    entries.iter().map( |entry| {
+        let value: i64 = entry.order.fills.iter()
+                              .map( |fill| fill.qty.get() as i64 * fill.price as i64 )
+                              .sum();
+        if entry.order.direction == "buy" { -value } else { value }
+    }).sum()
+}
+
+/// The `schema` number `to_json` writes. Bumped whenever a field is renamed or removed;
+/// added fields don't bump it.
+pub static BLOTTER_SCHEMA: u32 = 1;
+
+// What to_json writes. Entries go out as a list, oldest order id first, rather than as the
+// id-keyed map -- JSON object keys have to be strings
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    schema: u32,
+    entries: Vec<BlotterEntry>,
+    attempts: Vec<SendAttempt>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Blotter {
+    entries: BTreeMap<i32, BlotterEntry>,
+    attempts: Vec<SendAttempt>,
+}
+
+impl Blotter {
+    pub fn new() -> Blotter {
+        Blotter { entries: BTreeMap::new(), attempts: vec![] }
+    }
+
+    /// The whole blotter as one JSON object:
+    ///
+    /// * `schema` -- `BLOTTER_SCHEMA`
+    /// * `entries` -- every `BlotterEntry` (tag, the latest `OrderResponse` with its fills,
+    ///   payload and payload checksum), oldest order id first
+    /// * `attempts` -- every `SendAttempt`, oldest first
+    ///
+    /// # Example
+    /// ```
+    /// use market::blotter::Blotter;
+    /// let mut blotter = Blotter::new();
+    /// let order: market::OrderResponse = market::decode::decode( market::fixtures::ORDER ).unwrap();
+    /// blotter.record( "mean-revert", order );
+    ///
+    /// let json = blotter.to_json().unwrap();
+    /// assert!( json.contains( r#""schema":1"# ) );
+    /// let back = Blotter::from_json( &json ).unwrap();
+    /// assert_eq!( back.tag( 12345 ), Some( "mean-revert" ) );
+    /// assert_eq!( back.position( "mean-revert" ), blotter.position( "mean-revert" ) );
+    /// assert_eq!( back.to_json().unwrap(), json );
+    /// ```
+    pub fn to_json( &self ) -> Result<String, StockfighterErr> {
+        let snapshot = Snapshot {
+            schema: BLOTTER_SCHEMA,
+            entries: self.entries.values().cloned().collect(),
+            attempts: self.attempts.clone(),
+        };
+        Ok( try!( serde_json::to_string( &snapshot ) ) )
+    }
+
+    /// Reads back what `to_json` wrote.
+    pub fn from_json( json: &str ) -> Result<Blotter, StockfighterErr> {
+        let snapshot: Snapshot = try!( serde_json::from_str( json ) );
+        Ok( Blotter {
+            entries: snapshot.entries.into_iter().map( |entry| ( entry.order.id, entry ) ).collect(),
+            attempts: snapshot.attempts,
+        })
+    }
+
+    /// Sends `order` and records the response under `tag`. Rejected orders come back as an
+    /// error and get no entry -- they have no id to key on -- but like every attempt they're
+    /// in the send log (see `attempts`).
+    ///
+    /// # Example
+    /// ```
+    /// let mut blotter = market::blotter::Blotter::new();
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// blotter.submit( "mean-revert", &order ).unwrap();
+    /// println!("mean-revert is at {} shares", blotter.position( "mean-revert" ) );
+    /// ```
+    pub fn submit( &mut self, tag: &str, order: &Order ) -> Result<OrderResponse, StockfighterErr> {
+        self.submit_with( tag, order, &Auth::Env )
+    }
+
+    /// Same as submit, authenticating with `auth`.
+    pub fn submit_with( &mut self, tag: &str, order: &Order, auth: &Auth ) -> Result<OrderResponse, StockfighterErr> {
+        let payload = try!( order.encode_order() );
+        let sent = order.send_encoded( &payload, auth );
+        let attempt = SendAttempt {
+            attempt: self.attempts.len() as u64 + 1,
+            tag: tag.to_owned(),
+            payload_checksum: checksum( payload.as_bytes() ),
+            payload: payload.clone(),
+            order_id: sent.as_ref().ok().map( |&( ref response, _ )| response.id ),
+            error: sent.as_ref().err().map( |err| err.to_string() ),
+        };
+        self.attempts.push( attempt );
+        let ( response, _ ) = try!( sent );
+        self.record( tag, response.clone() );
+        if let Some( entry ) = self.entries.get_mut( &response.id ) {
+            entry.payload_checksum = checksum( payload.as_bytes() );
+            entry.payload = payload;
+        }
+        Ok( response )
+    }
+
+    /// Records a response under `tag`, replacing anything already recorded for that order id
+    /// apart from the sent payload.
+    pub fn record( &mut self, tag: &str, mut order: OrderResponse ) {
+        let incoming = ::std::mem::replace( &mut order.fills, vec![] );
+        merge_fills( &mut order.fills, incoming );
+        let ( payload, payload_checksum ) = match self.entries.remove( &order.id ) {
+            Some( previous ) => ( previous.payload, previous.payload_checksum ),
+            None => ( String::new(), String::new() ),
+        };
+        self.entries.insert( order.id, BlotterEntry {
+            tag: tag.to_owned(),
+            order: order,
+            payload: payload,
+            payload_checksum: payload_checksum,
+        });
+    }
+
+    /// Takes a newer response for an order that's already on the blotter (eg. after polling
+    /// its status), keeping its tag. Fills already known are not added again. Returns false if
+    /// the order id isn't known.
+    pub fn update( &mut self, mut order: OrderResponse ) -> bool {
+        match self.entries.get_mut( &order.id ) {
+            Some( entry ) => {
+                let mut fills = ::std::mem::replace( &mut entry.order.fills, vec![] );
+                merge_fills( &mut fills, ::std::mem::replace( &mut order.fills, vec![] ) );
+                order.fills = fills;
+                entry.order = order;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Adds a single fill for order `id` (eg. from the executions stream). Returns true if it
+    /// was new, false if it had already been seen or the order isn't on the blotter.
+    pub fn ingest_fill( &mut self, id: i32, fill: OrderFill ) -> bool {
+        match self.entries.get_mut( &id ) {
+            Some( entry ) => merge_fills( &mut entry.order.fills, vec![ fill ] ) == 1,
+            None => false,
+        }
+    }
+
+    /// Cancels every order still open under `tag` and takes each one's final state, so fills
+    /// that landed after the submit response are counted and the next order doesn't stack on
+    /// top of one still resting. Every open order is tried; the first error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// let mut blotter = market::blotter::Blotter::new();
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// blotter.submit( "buyer", &order ).unwrap();
+    /// blotter.settle( "buyer" ).unwrap();
+    /// println!("buyer ended up with {} shares", blotter.position( "buyer" ) );
+    /// ```
+    pub fn settle( &mut self, tag: &str ) -> Result<(), StockfighterErr> {
+        self.settle_with( tag, &Auth::Env )
+    }
+
+    /// Same as settle, authenticating with `auth`.
+    pub fn settle_with( &mut self, tag: &str, auth: &Auth ) -> Result<(), StockfighterErr> {
+        let open: Vec<OrderResponse> = self.by_tag( tag ).iter()
+                                           .filter( |entry| entry.order.open )
+                                           .map( |entry| entry.order.clone() )
+                                           .collect();
+        let mut first_err = None;
+        for order in open {
+            match order.cancel_with( auth ) {
+                Ok( last ) => { self.update( last ); },
+                Err( err ) => if first_err.is_none() { first_err = Some( err ) },
+            }
+        }
+        match first_err {
+            Some( err ) => Err( err ),
+            None => Ok( () ),
+        }
+    }
+
+    /// Every send attempt made through `submit`, oldest first, including the failed ones.
+    pub fn attempts( &self ) -> &[SendAttempt] {
+        &self.attempts
+    }
+
+    pub fn get( &self, id: i32 ) -> Option<&BlotterEntry> {
+        self.entries.get( &id )
+    }
+
+    /// The tag an order was sent under.
+    pub fn tag( &self, id: i32 ) -> Option<&str> {
+        self.entries.get( &id ).map( |entry| entry.tag.as_str() )
+    }
+
+    /// Every entry, oldest order id first.
+    pub fn entries( &self ) -> Vec<&BlotterEntry> {
+        self.entries.values().collect()
+    }
+
+    pub fn by_tag( &self, tag: &str ) -> Vec<&BlotterEntry> {
+        self.entries.values().filter( |entry| entry.tag == tag ).collect()
+    }
+
+    /// Net shares filled for `tag` -- buys positive, sells negative.
+    pub fn position( &self, tag: &str ) -> i32 {
+        net_position( self.by_tag( tag ) )
+    }
+
+    /// Net cash flow in cents for `tag` -- sells add, buys subtract. Together with `position`
+    /// and a mark price that gives the tag's P&L.
+    pub fn cash( &self, tag: &str ) -> i64 {
+        net_cash( self.by_tag( tag ) )
+    }
+
+    /// Every symbol `tag` has sent orders in, sorted.
+    pub fn symbols( &self, tag: &str ) -> Vec<String> {
+        let mut symbols: Vec<String> = self.by_tag( tag ).iter().map( |entry| entry.order.symbol.clone() ).collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// `position`, counting only orders in `symbol`.
+    pub fn position_in( &self, tag: &str, symbol: &str ) -> i32 {
+        net_position( self.by_tag_in( tag, symbol ) )
+    }
+
+    /// `cash`, counting only orders in `symbol`.
+    pub fn cash_in( &self, tag: &str, symbol: &str ) -> i64 {
+        net_cash( self.by_tag_in( tag, symbol ) )
+    }
+
+    fn by_tag_in( &self, tag: &str, symbol: &str ) -> Vec<&BlotterEntry> {
+        self.by_tag( tag ).into_iter().filter( |entry| entry.order.symbol == symbol ).collect()
+    }
+}
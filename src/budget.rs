@@ -0,0 +1,175 @@
+// Sharing one rate limit between market data and orders.
+//
+// A venue's rate limit covers every request, but a bot's requests aren't equal. If the quote
+// poller and the book sampler are allowed to spend the whole allowance, the cancel that would
+// get us out of a bad position waits behind them. RateBudget splits the allowance: a budget
+// for market data reads, one for order writes, and a small reserve that only cancels and
+// flattening orders may dip into once the write budget is spent. Reads can never touch writes
+// or the reserve, so data polling can't starve risk-reducing actions.
+//
+// Each budget is a token bucket that refills continuously and holds up to one second's worth.
+// Share one RateBudget (in an Arc) between the pollers and the order queue.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use clock::{Clock, SystemClock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
+    /// Quotes, books, order status
+    Read,
+    /// New orders
+    Write,
+    /// Cancels and risk-reducing orders: the write budget first, then the reserve
+    Emergency,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new( rate: f64, now: Instant ) -> Bucket {
+        let rate = rate.max( 0.0 );
+        // Full to begin with, except that a zero budget has to start (and stay) empty
+        let tokens = if rate > 0.0 { rate.max( 1.0 ) } else { 0.0 };
+        Bucket { rate: rate, tokens: tokens, updated: now }
+    }
+
+    fn refill( &mut self, now: Instant ) {
+        if now > self.updated {
+            let elapsed = now - self.updated;
+            let seconds = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+            self.tokens = ( self.tokens + seconds * self.rate ).min( self.rate.max( 1.0 ) );
+            self.updated = now;
+        }
+    }
+
+    fn take( &mut self, now: Instant ) -> bool {
+        self.refill( now );
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    // How long until a token is there; None if the bucket never refills
+    fn wait( &mut self, now: Instant ) -> Option<Duration> {
+        self.refill( now );
+        if self.tokens >= 1.0 {
+            return Some( Duration::from_secs( 0 ) );
+        }
+        if self.rate <= 0.0 {
+            return None;
+        }
+        let nanos = ( ( 1.0 - self.tokens ) / self.rate * 1e9 ).ceil() as u64;
+        Some( Duration::new( nanos / 1_000_000_000, ( nanos % 1_000_000_000 ) as u32 ) )
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    read: Bucket,
+    write: Bucket,
+    reserve: Bucket,
+}
+
+#[derive(Debug)]
+pub struct RateBudget {
+    buckets: Mutex<Buckets>,
+    clock: Arc<Clock>,
+}
+
+impl RateBudget {
+    /// `reads` and `writes` requests a second for each class, plus `reserve` a second held
+    /// back for emergencies.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use market::budget::{Class, RateBudget};
+    /// use market::poll::Poller;
+    /// let budget = Arc::new( RateBudget::new( 6.0, 3.0, 1.0 ) );
+    /// let client = market::StockfighterClient::new();
+    /// let quote = Poller::new( Duration::from_millis( 100 ) )
+    ///               .budget( budget.clone() )
+    ///               .deadline( Duration::from_secs( 10 ) )
+    ///               .run( || client.quote( "TESTEX", "FOOBAR" ), |quote| quote.ask > 0 )
+    ///               .unwrap();
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), quote.ask, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// // The same budget paces orders alongside the polling
+    /// if budget.take( Class::Write ) {
+    ///   order.process_order().unwrap();
+    /// }
+    /// ```
+    pub fn new( reads: f64, writes: f64, reserve: f64 ) -> RateBudget {
+        RateBudget::with_clock( reads, writes, reserve, Arc::new( SystemClock ) )
+    }
+
+    /// As `new`, refilling by `clock` rather than the system clock.
+    pub fn with_clock( reads: f64, writes: f64, reserve: f64, clock: Arc<Clock> ) -> RateBudget {
+        let now = clock.now();
+        RateBudget {
+            buckets: Mutex::new( Buckets {
+                read: Bucket::new( reads, now ),
+                write: Bucket::new( writes, now ),
+                reserve: Bucket::new( reserve, now ),
+            }),
+            clock: clock,
+        }
+    }
+
+    fn lock( &self ) -> ::std::sync::MutexGuard<Buckets> {
+        self.buckets.lock().unwrap_or_else( |poisoned| poisoned.into_inner() )
+    }
+
+    /// Spends one request of `class` if the budget allows it now.
+    pub fn try_take( &self, class: Class ) -> bool {
+        let now = self.clock.now();
+        let mut buckets = self.lock();
+        match class {
+            Class::Read => buckets.read.take( now ),
+            Class::Write => buckets.write.take( now ),
+            Class::Emergency => buckets.write.take( now ) || buckets.reserve.take( now ),
+        }
+    }
+
+    /// How long until a request of `class` could go; zero if one can go now, None if the
+    /// budget for it is zero.
+    pub fn wait_time( &self, class: Class ) -> Option<Duration> {
+        let now = self.clock.now();
+        let mut buckets = self.lock();
+        match class {
+            Class::Read => buckets.read.wait( now ),
+            Class::Write => buckets.write.wait( now ),
+            Class::Emergency => match ( buckets.write.wait( now ), buckets.reserve.wait( now ) ) {
+                ( Some( a ), Some( b ) ) => Some( a.min( b ) ),
+                ( a, None ) => a,
+                ( None, b ) => b,
+            },
+        }
+    }
+
+    /// Blocks until a request of `class` is allowed, then spends it and returns true. Returns
+    /// false at once if the budget for `class` is zero, rather than waiting forever.
+    pub fn take( &self, class: Class ) -> bool {
+        loop {
+            if self.try_take( class ) {
+                return true;
+            }
+            match self.wait_time( class ) {
+                Some( wait ) => self.clock.sleep( wait.max( Duration::from_millis( 1 ) ) ),
+                None => return false,
+            }
+        }
+    }
+}
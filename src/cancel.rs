@@ -0,0 +1,88 @@
+// Stopping long-running work from outside.
+//
+// Pollers, supervised workers and execution loops can run for minutes. A CancelToken is a
+// cheap, clonable "please stop" shared between whoever might want to abort the work and the
+// work itself: hand a clone to each operation, call `cancel` once, and every one of them
+// returns `StockfighterErr::Cancelled` at its next check. `sleep` checks while it waits, so a
+// loop parked between polls wakes up promptly instead of finishing its interval.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use StockfighterErr;
+use clock::Clock;
+
+// Longest a cancelled sleep can keep going before it notices
+static SLEEP_STEP_MS: u64 = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use market::cancel::CancelToken;
+    /// use market::poll::Poller;
+    /// let token = CancelToken::new();
+    /// let watcher = token.clone();
+    /// std::thread::spawn( move || {
+    ///   let client = market::StockfighterClient::new();
+    ///   let filled = Poller::new( Duration::from_millis( 250 ) )
+    ///                  .cancel_with( watcher )
+    ///                  .run( || client.quote( "TESTEX", "FOOBAR" ), |quote| quote.last > 10000 );
+    ///   println!("{:?}", filled.is_ok() );
+    /// });
+    /// token.cancel();
+    /// ```
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// A token that is cancelled whenever `flag` is set, and sets it when cancelled.
+    pub fn from_flag( flag: Arc<AtomicBool> ) -> CancelToken {
+        CancelToken { flag: flag }
+    }
+
+    /// The underlying flag, for code that takes an `Arc<AtomicBool>`.
+    pub fn flag( &self ) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+
+    /// Cancels every clone of this token. Cancelling twice is harmless.
+    pub fn cancel( &self ) {
+        self.flag.store( true, Ordering::SeqCst );
+    }
+
+    pub fn is_cancelled( &self ) -> bool {
+        self.flag.load( Ordering::SeqCst )
+    }
+
+    /// Err(Cancelled) once the token has been cancelled -- `try!( token.check() )` at the top
+    /// of a loop.
+    pub fn check( &self ) -> Result<(), StockfighterErr> {
+        if self.is_cancelled() {
+            Err( StockfighterErr::Cancelled )
+        } else {
+            Ok( () )
+        }
+    }
+
+    /// Waits `duration` on `clock`, returning Err(Cancelled) early if the token is cancelled.
+    pub fn sleep( &self, clock: &Clock, duration: Duration ) -> Result<(), StockfighterErr> {
+        let step = Duration::from_millis( SLEEP_STEP_MS );
+        let mut left = duration;
+        loop {
+            try!( self.check() );
+            if left == Duration::from_secs( 0 ) {
+                return Ok( () );
+            }
+            let nap = if left < step { left } else { step };
+            clock.sleep( nap );
+            left = left - nap;
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Randomised order flow, for load testing.
+//
+// Chaos fires a stream of random orders at a venue at a fixed rate. Most are valid limit and
+// market orders around a price band; optionally a fraction are deliberately broken (negative
+// quantities, unknown order types, missing fields) and sent through raw::RawRequest. Useful
+// both for stress-testing your own infrastructure and for seeing how community-hosted server
+// implementations cope.
+
+use hyper::method::Method;
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clock::{Clock, SystemClock};
+use raw::RawRequest;
+use {Order, Qty, StockfighterErr};
+
+/// xorshift64* -- small, fast and plenty random enough to pick order parameters with
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new( seed: u64 ) -> Rng {
+        // xorshift gets stuck at zero forever
+        Rng { state: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed } }
+    }
+
+    /// Seeded from the clock.
+    pub fn from_time() -> Rng {
+        let now = SystemTime::now().duration_since( UNIX_EPOCH ).unwrap_or( Duration::from_secs( 0 ) );
+        Rng::new( now.as_secs() ^ ( ( now.subsec_nanos() as u64 ) << 32 ) )
+    }
+
+    pub fn next_u64( &mut self ) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul( 0x2545F4914F6CDD1D )
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64( &mut self ) -> f64 {
+        ( self.next_u64() >> 11 ) as f64 / ( 1u64 << 53 ) as f64
+    }
+
+    /// Uniform in [low, high]
+    pub fn range( &mut self, low: i32, high: i32 ) -> i32 {
+        if high <= low {
+            return low;
+        }
+        let span = ( high as i64 - low as i64 + 1 ) as u64;
+        ( low as i64 + ( self.next_u64() % span ) as i64 ) as i32
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChaosReport {
+    pub sent: usize,
+    /// Orders the server accepted (ok: true)
+    pub accepted: usize,
+    /// Orders the server answered but refused (ok: false, or a non-2xx raw response)
+    pub rejected: usize,
+    /// Requests that didn't get an answer at all
+    pub errors: usize,
+}
+
+pub struct Chaos {
+    account: String,
+    venue: String,
+    stock: String,
+    interval: Duration,
+    invalid_ratio: f64,
+    prices: (i32, i32),
+    max_qty: u32,
+    rng: Rng,
+    clock: Arc<Clock>,
+}
+
+impl Chaos {
+    /// Defaults: 10 orders a second, all valid, priced 10.00 - 100.00, up to 100 shares.
+    ///
+    /// # Example
+    /// ```
+    /// let report = market::chaos::Chaos::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                         "FOOBAR".to_owned() )
+    ///                  .per_second( 50.0 )
+    ///                  .invalid_ratio( 0.1 )
+    ///                  .run( 500 );
+    /// println!("{:?}", report );
+    /// ```
+    pub fn new( account: String, venue: String, stock: String ) -> Chaos {
+        Chaos {
+            account: account,
+            venue: venue,
+            stock: stock,
+            interval: Duration::from_millis( 100 ),
+            invalid_ratio: 0.0,
+            prices: ( 1000, 10000 ),
+            max_qty: 100,
+            rng: Rng::from_time(),
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    pub fn per_second( mut self, rate: f64 ) -> Chaos {
+        let nanos = if rate > 0.0 { ( 1e9 / rate ) as u64 } else { 0 };
+        self.interval = Duration::new( nanos / 1_000_000_000, ( nanos % 1_000_000_000 ) as u32 );
+        self
+    }
+
+    /// Fraction (0.0 - 1.0) of orders that are deliberately malformed.
+    pub fn invalid_ratio( mut self, ratio: f64 ) -> Chaos {
+        self.invalid_ratio = ratio.max( 0.0 ).min( 1.0 );
+        self
+    }
+
+    /// Price band in cents, inclusive.
+    pub fn prices( mut self, low: i32, high: i32 ) -> Chaos {
+        self.prices = ( low, high );
+        self
+    }
+
+    pub fn max_qty( mut self, qty: u32 ) -> Chaos {
+        self.max_qty = qty.max( 1 );
+        self
+    }
+
+    /// Seeds the random choices, so a run that turned something up can be replayed exactly.
+    /// Without a seed, runs are seeded from the clock.
+    pub fn seed( mut self, seed: u64 ) -> Chaos {
+        self.rng = Rng::new( seed );
+        self
+    }
+
+    /// Paces orders with `clock` rather than really sleeping between them.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> Chaos {
+        self.clock = clock;
+        self
+    }
+
+    /// A random, valid order.
+    pub fn next_order( &mut self ) -> Order {
+        let direction = if self.rng.range( 0, 1 ) == 0 { "buy" } else { "sell" };
+        let order_types = [ "limit", "market", "fill-or-kill", "immediate-or-cancel" ];
+        let order_type = order_types[ self.rng.range( 0, 3 ) as usize ];
+        Order::new( self.account.clone(),
+                    self.venue.clone(),
+                    self.stock.clone(),
+                    self.rng.range( self.prices.0, self.prices.1 ),
+                    Qty( self.rng.range( 1, self.max_qty.min( i32::max_value() as u32 ) as i32 ) as u32 ),
+                    direction.to_owned(),
+                    order_type.to_owned() )
+    }
+
+    /// A random order broken in one of a few ways, ready to send.
+    pub fn next_invalid( &mut self ) -> RawRequest {
+        let order = self.next_order();
+        let body = match self.rng.range( 0, 4 ) {
+            0 => format!(r#"{{"account":"{}","venue":"{}","stock":"{}","price":{},"qty":{},"direction":"{}","orderType":"limit"}}"#,
+                         order.account, order.venue, order.stock, order.price, -( order.qty.get() as i64 ), order.direction),
+            1 => format!(r#"{{"account":"{}","venue":"{}","stock":"{}","price":{},"qty":{},"direction":"{}","orderType":"sideways"}}"#,
+                         order.account, order.venue, order.stock, order.price, order.qty, order.direction),
+            2 => format!(r#"{{"account":"{}","venue":"{}","stock":"{}","qty":{}}}"#,
+                         order.account, order.venue, order.stock, order.qty),
+            3 => format!(r#"{{"account":"{}","venue":"{}","stock":"{}","price":{},"qty":{},"direction":"up","orderType":"limit"}}"#,
+                         order.account, order.venue, order.stock, -order.price, order.qty),
+            // Truncated JSON
+            _ => "{\"account\":".to_owned(),
+        };
+        let mut request = RawRequest::new( Method::Post,
+                                           format!("/venues/{}/stocks/{}/orders", order.venue, order.stock) )
+                              .with_auth();
+        request.body = Some( body );
+        request
+    }
+
+    fn send_one( &mut self, report: &mut ChaosReport ) -> Result<(), StockfighterErr> {
+        report.sent += 1;
+        if self.rng.next_f64() < self.invalid_ratio {
+            let response = try!( self.next_invalid().send() );
+            if response.status >= 200 && response.status < 300 {
+                report.accepted += 1;
+            } else {
+                report.rejected += 1;
+            }
+        } else {
+            match self.next_order().process_order() {
+                Ok( _ ) => report.accepted += 1,
+                Err( StockfighterErr::Api( _ ) ) => report.rejected += 1,
+                Err( err ) => return Err( err ),
+            }
+        }
+        Ok( () )
+    }
+
+    /// Sends `count` orders at the configured rate and tallies how they went.
+    pub fn run( &mut self, count: usize ) -> ChaosReport {
+        let mut report = ChaosReport::default();
+        for i in 0..count {
+            if self.send_one( &mut report ).is_err() {
+                report.errors += 1;
+            }
+            if i + 1 < count {
+                self.clock.sleep( self.interval );
+            }
+        }
+        report
+    }
+}
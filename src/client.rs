@@ -0,0 +1,247 @@
+// A long lived client for tight polling loops.
+//
+// The methods on Quote / OrderBook / etc. each build a fresh hyper Client and send
+// `Connection: close`, which means every call pays for a new TCP + TLS handshake. That's fine
+// for the occasional call, but a bot polling the quote endpoint several times a second spends
+// most of its time connecting. StockfighterClient keeps one hyper Client around and leaves
+// keep-alive on, so a rapid sequence of requests goes out over the same connection.
+//
+// The URL and response body are written into scratch buffers owned by the client, so once
+// they've grown to size a polling loop stops allocating for them on every call.
+//
+// Venue stock listings rarely change during a level, so `stocks` caches them per venue until
+// explicitly invalidated.
+//
+// A response with `ok: false` comes back as StockfighterErr::Api rather than a struct.
+//
+// `set_strict( true )` turns on schema drift reporting for everything the client fetches (see
+// the schema module).
+//
+// `touch`, `spread` and `mid` only need the best bid and ask, which the quote endpoint gives
+// in a few hundred bytes against the kilobytes of a full book, so that's what they use unless
+// told otherwise with `set_top_of_book`.
+
+use hyper::Client;
+use serde;
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use decode;
+use poll;
+use schema::{DriftLog, Schema};
+use {Meta, OrderBook, Quote, Route, Stock, StockfighterAPI, StockfighterErr, StockfighterVenueStocks};
+
+/// Where `touch` and friends get the best bid and ask from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOfBook {
+    /// The quote endpoint: small and cheap to parse
+    Quote,
+    /// The full order book: bigger, but the quote endpoint can lag it on some servers
+    Book,
+}
+
+/// The scratch buffers make this client single threaded -- give each polling thread its own.
+pub struct StockfighterClient {
+    client: Client,
+    url: RefCell<String>,
+    body: RefCell<String>,
+    drift: RefCell<Option<DriftLog>>,
+    stocks: RefCell<HashMap<String, Vec<Stock>>>,
+    top_of_book: Cell<TopOfBook>,
+}
+
+impl StockfighterClient {
+    pub fn new() -> StockfighterClient {
+        StockfighterClient {
+            client: Client::new(),
+            url: RefCell::new( String::new() ),
+            body: RefCell::new( String::new() ),
+            drift: RefCell::new( None ),
+            stocks: RefCell::new( HashMap::new() ),
+            top_of_book: Cell::new( TopOfBook::Quote ),
+        }
+    }
+
+    /// Turns schema drift reporting on or off. Turning it off forgets what was reported.
+    pub fn set_strict( &self, strict: bool ) {
+        *self.drift.borrow_mut() = if strict { Some( DriftLog::new() ) } else { None };
+    }
+
+    /// Chooses where `touch`, `spread` and `mid` read the best bid and ask from.
+    pub fn set_top_of_book( &self, source: TopOfBook ) {
+        self.top_of_book.set( source );
+    }
+
+    /// Unknown (endpoint, field) pairs seen since strict mode was turned on.
+    pub fn drift( &self ) -> Vec<(String, String)> {
+        match *self.drift.borrow() {
+            Some( ref log ) => log.seen(),
+            None => vec![],
+        }
+    }
+
+    // Only used for idempotent GETs -- orders still go through Order::process_order so a
+    // retried or half-sent request can never submit the same order twice
+    fn get<T: serde::Deserialize + Schema>( &self, route: Route ) -> Result<T, StockfighterErr> {
+        self.fetch( route, false ).map( |( value, _ )| value )
+    }
+
+    fn get_with_meta<T: serde::Deserialize + Schema>( &self, route: Route ) -> Result<(T, Meta), StockfighterErr> {
+        let ( value, meta ) = try!( self.fetch( route, true ) );
+        Ok( ( value, meta.unwrap_or_else( Meta::default ) ) )
+    }
+
+    // Collecting the headers costs allocations, so it's only done when asked for
+    fn fetch<T: serde::Deserialize + Schema>( &self, route: Route, want_meta: bool )
+                                              -> Result<(T, Option<Meta>), StockfighterErr> {
+        let mut url = self.url.borrow_mut();
+        try!( route.write_url( &mut url ) );
+        let mut body = self.body.borrow_mut();
+        body.clear();
+        trace_span!( "http", op = route.name(), url = url.as_str() );
+        let started = Instant::now();
+        let mut response = try!( self.client.get( url.as_str() ).send() );
+        let meta = if want_meta { Some( Meta::from_response( &response, started ) ) } else { None };
+        try!( response.read_to_string( &mut *body ) );
+        if let Some( ref mut log ) = *self.drift.borrow_mut() {
+            log.check::<T>( route.name(), &body );
+        }
+        let deserialized = try!( decode::checked( &body ) );
+        Ok( ( deserialized, meta ) )
+    }
+
+    /// Opens the connection to the API host ahead of time with a throwaway heartbeat request,
+    /// so the first real call of a time-critical level doesn't pay for the TCP + TLS
+    /// handshake. Returns how long the warm-up took.
+    ///
+    /// # Example
+    /// ```
+    /// let client = market::StockfighterClient::new();
+    /// let took = client.warm_up().unwrap();
+    /// println!("connected in {:?}", took );
+    /// // ... the level starts, and this quote goes out on the already-open connection
+    /// let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    /// ```
+    pub fn warm_up( &self ) -> Result<Duration, StockfighterErr> {
+        let started = Instant::now();
+        let _: StockfighterAPI = try!( self.get( Route::Heartbeat ) );
+        Ok( started.elapsed() )
+    }
+
+    /// Fetches a quote over the client's kept-alive connection.
+    ///
+    /// # Example
+    /// ```
+    /// let client = market::StockfighterClient::new();
+    /// for _ in 0..10 {
+    ///   let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    ///   println!("bid {} ask {}", quote.bid, quote.ask );
+    /// }
+    /// ```
+    pub fn quote( &self, venue: &str, symbol: &str ) -> Result<Quote, StockfighterErr> {
+        self.get( Route::Quote { venue: venue, stock: symbol } )
+    }
+
+    /// Polls `symbol`'s quote every `interval` until `predicate` accepts one, giving up with
+    /// `TimedOut` after `deadline`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// let client = market::StockfighterClient::new();
+    /// let quote = client.poll_quote( "TESTEX", "FOOBAR", Duration::from_millis( 250 ),
+    ///                                Duration::from_secs( 30 ),
+    ///                                |quote| quote.bid > 0 && quote.ask - quote.bid < 10 );
+    /// ```
+    pub fn poll_quote<P>( &self, venue: &str, symbol: &str, interval: Duration, deadline: Duration,
+                          predicate: P ) -> Result<Quote, StockfighterErr>
+        where P: FnMut( &Quote ) -> bool
+    {
+        poll::poll_until( interval, deadline, || self.quote( venue, symbol ), predicate )
+    }
+
+    /// Same as `quote`, also returning the HTTP status, headers and round trip time.
+    pub fn quote_with_meta( &self, venue: &str, symbol: &str ) -> Result<(Quote, Meta), StockfighterErr> {
+        self.get_with_meta( Route::Quote { venue: venue, stock: symbol } )
+    }
+
+    /// Fetches the full order book over the client's kept-alive connection.
+    pub fn order_book( &self, venue: &str, symbol: &str ) -> Result<OrderBook, StockfighterErr> {
+        self.get( Route::OrderBook { venue: venue, stock: symbol } )
+    }
+
+    /// Same as `order_book`, also returning the HTTP status, headers and round trip time.
+    pub fn order_book_with_meta( &self, venue: &str, symbol: &str ) -> Result<(OrderBook, Meta), StockfighterErr> {
+        self.get_with_meta( Route::OrderBook { venue: venue, stock: symbol } )
+    }
+
+    /// The stocks listed on `venue`, fetched on first use and cached after that.
+    pub fn stocks( &self, venue: &str ) -> Result<Vec<Stock>, StockfighterErr> {
+        if let Some( stocks ) = self.stocks.borrow().get( venue ) {
+            return Ok( stocks.clone() );
+        }
+        let listing: StockfighterVenueStocks = try!( self.get( Route::VenueStocks { venue: venue } ) );
+        self.stocks.borrow_mut().insert( venue.to_owned(), listing.symbols.clone() );
+        Ok( listing.symbols )
+    }
+
+    /// Forgets the cached listing for `venue`, or for every venue with None.
+    pub fn invalidate_stocks( &self, venue: Option<&str> ) {
+        match venue {
+            Some( venue ) => { self.stocks.borrow_mut().remove( venue ); },
+            None => self.stocks.borrow_mut().clear(),
+        }
+    }
+
+    /// Quotes a batch of symbols back to back on the same connection, one result per symbol
+    /// in the order they were given. A failure on one symbol doesn't stop the rest.
+    pub fn quotes( &self, venue: &str, symbols: &[&str] ) -> Vec<Result<Quote, StockfighterErr>> {
+        symbols.iter().map( |symbol| self.quote( venue, symbol ) ).collect()
+    }
+
+    /// The best bid and ask for `symbol`, None for an empty side.
+    ///
+    /// # Example
+    /// ```
+    /// let client = market::StockfighterClient::new();
+    /// match client.touch( "TESTEX", "FOOBAR" ).unwrap() {
+    ///   ( Some( bid ), Some( ask ) ) => println!("{} / {}", bid, ask ),
+    ///   _ => println!("one sided"),
+    /// }
+    /// println!("spread {:?}, mid {:?}", client.spread( "TESTEX", "FOOBAR" ).unwrap(),
+    ///          client.mid( "TESTEX", "FOOBAR" ).unwrap() );
+    /// ```
+    pub fn touch( &self, venue: &str, symbol: &str ) -> Result<(Option<i32>, Option<i32>), StockfighterErr> {
+        let positive = |price: i32| if price > 0 { Some( price ) } else { None };
+        match self.top_of_book.get() {
+            TopOfBook::Quote => {
+                let quote = try!( self.quote( venue, symbol ) );
+                Ok( ( positive( quote.bid ), positive( quote.ask ) ) )
+            },
+            TopOfBook::Book => {
+                let book = try!( self.order_book( venue, symbol ) );
+                Ok( ( book.bids.iter().map( |bid| bid.price ).max(),
+                      book.asks.iter().map( |ask| ask.price ).min() ) )
+            },
+        }
+    }
+
+    /// Ask minus bid in cents, None if either side is empty.
+    pub fn spread( &self, venue: &str, symbol: &str ) -> Result<Option<i32>, StockfighterErr> {
+        match try!( self.touch( venue, symbol ) ) {
+            ( Some( bid ), Some( ask ) ) => Ok( Some( ask - bid ) ),
+            _ => Ok( None ),
+        }
+    }
+
+    /// Halfway between bid and ask (rounded down), None if either side is empty.
+    pub fn mid( &self, venue: &str, symbol: &str ) -> Result<Option<i32>, StockfighterErr> {
+        match try!( self.touch( venue, symbol ) ) {
+            ( Some( bid ), Some( ask ) ) => Ok( Some( ( bid + ask ) / 2 ) ),
+            _ => Ok( None ),
+        }
+    }
+}
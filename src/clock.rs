@@ -0,0 +1,126 @@
+// Time sources.
+//
+// Anything in the crate that reads the time or sleeps does it through a Clock, so tests and
+// backtests can run against a MockClock they advance by hand instead of waiting on the wall
+// clock. In normal use nothing changes: every component defaults to SystemClock.
+//
+// Instants can't be conjured from thin air, so a MockClock's monotonic time is "the Instant it
+// was created, plus however far it has been advanced". Only differences between Instants mean
+// anything anyway.
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub trait Clock: Debug + Send + Sync {
+    /// Monotonic time, for measuring intervals and scheduling.
+    fn now( &self ) -> Instant;
+    /// Wall-clock time, for timestamps and deadlines.
+    fn wall( &self ) -> SystemTime;
+    /// Waits `duration`.
+    fn sleep( &self, duration: Duration );
+
+    /// Wall-clock time in milliseconds since the Unix epoch.
+    fn millis( &self ) -> u64 {
+        match self.wall().duration_since( UNIX_EPOCH ) {
+            Ok( since ) => duration_millis( since ),
+            Err( _ ) => 0,
+        }
+    }
+}
+
+/// `duration` in whole milliseconds, saturating rather than overflowing.
+pub fn duration_millis( duration: Duration ) -> u64 {
+    duration.as_secs().saturating_mul( 1000 ).saturating_add( ( duration.subsec_nanos() / 1_000_000 ) as u64 )
+}
+
+/// The real clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now( &self ) -> Instant {
+        Instant::now()
+    }
+
+    fn wall( &self ) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep( &self, duration: Duration ) {
+        thread::sleep( duration );
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, so keep one to advance
+/// and hand the others to the components under test. Sleeping on it advances it instantly.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use market::budget::{Class, RateBudget};
+/// use market::clock::{Clock, MockClock};
+/// let clock = MockClock::new();
+/// let budget = RateBudget::with_clock( 2.0, 1.0, 0.0, Arc::new( clock.clone() ) );
+/// assert!( budget.try_take( Class::Read ) );
+/// assert!( budget.try_take( Class::Read ) );
+/// assert!( !budget.try_take( Class::Read ) );
+/// clock.advance( Duration::from_millis( 500 ) );
+/// assert!( budget.try_take( Class::Read ) );
+/// assert!( !budget.try_take( Class::Read ) );
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    wall_start: SystemTime,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    /// A mock clock whose wall time starts at the Unix epoch.
+    pub fn new() -> MockClock {
+        MockClock::starting_at( UNIX_EPOCH )
+    }
+
+    /// A mock clock whose wall time starts at `wall`.
+    pub fn starting_at( wall: SystemTime ) -> MockClock {
+        MockClock {
+            start: Instant::now(),
+            wall_start: wall,
+            elapsed: Arc::new( Mutex::new( Duration::from_secs( 0 ) ) ),
+        }
+    }
+
+    /// Moves time forward by `by`, for this clock and every clone of it.
+    pub fn advance( &self, by: Duration ) {
+        let mut elapsed = self.elapsed.lock().unwrap_or_else( |poisoned| poisoned.into_inner() );
+        *elapsed = *elapsed + by;
+    }
+
+    /// How far the clock has been advanced since it was created.
+    pub fn elapsed( &self ) -> Duration {
+        *self.elapsed.lock().unwrap_or_else( |poisoned| poisoned.into_inner() )
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> MockClock {
+        MockClock::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now( &self ) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn wall( &self ) -> SystemTime {
+        self.wall_start + self.elapsed()
+    }
+
+    fn sleep( &self, duration: Duration ) {
+        self.advance( duration );
+    }
+}
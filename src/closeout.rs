@@ -0,0 +1,164 @@
+// Going home flat.
+//
+// Several levels score the position at the end of the trading day, and a bot that's still
+// quoting when the bell goes carries whatever inventory it happened to have. EndOfDay is a
+// closing policy: from a set time it refuses orders that would add risk, and it produces the
+// orders that work the position back to zero through an ExecutionPolicy of your choosing --
+// passively for a while if given a window, then crossing the spread.
+//
+// The GM's trading-day clock isn't exposed through this crate, so the time is a wall-clock
+// deadline read through clock::Clock.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use {Order, OrderResponse, Qty, Quote};
+use clock::{Clock, SystemClock};
+use intent::{ExecutionPolicy, Intent, TouchPolicy};
+
+pub struct EndOfDay {
+    close_at: SystemTime,
+    window: Duration,
+    policy: Box<ExecutionPolicy>,
+    // Still the built-in TouchPolicy, which follows `with_clock`
+    default_policy: bool,
+    clock: Arc<Clock>,
+    announced: bool,
+}
+
+impl EndOfDay {
+    /// Stops adding risk and starts flattening at `close_at`, crossing the spread straight
+    /// away, in slices of at most 100 shares.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use market::closeout::EndOfDay;
+    /// use market::blotter::Blotter;
+    /// let mut close = EndOfDay::at( SystemTime::now() + Duration::from_secs( 600 ) )
+    ///                     .window( Duration::from_secs( 60 ) );
+    /// let mut blotter = Blotter::new();
+    /// let client = market::StockfighterClient::new();
+    /// // ... once per pass of the strategy loop:
+    /// let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    /// let open: Vec<market::OrderResponse> = blotter.by_tag( "mm" ).iter()
+    ///                                               .filter( |entry| entry.order.open )
+    ///                                               .map( |entry| entry.order.clone() )
+    ///                                               .collect();
+    /// if close.just_closed() {
+    ///   for order in &open {
+    ///     if !close.reduces( order, blotter.position( "mm" ) ) {
+    ///       order.cancel().ok();
+    ///     }
+    ///   }
+    /// }
+    /// for order in close.orders( "EXB123456", &quote, blotter.position( "mm" ), &open ) {
+    ///   blotter.submit( "mm", &order ).unwrap();
+    /// }
+    /// ```
+    pub fn at( close_at: SystemTime ) -> EndOfDay {
+        EndOfDay {
+            close_at: close_at,
+            window: Duration::from_secs( 0 ),
+            policy: Box::new( TouchPolicy::new( Qty( 100 ) ) ),
+            default_policy: true,
+            clock: Arc::new( SystemClock ),
+            announced: false,
+        }
+    }
+
+    /// Works the position passively for `window` after the close before crossing the spread.
+    pub fn window( mut self, window: Duration ) -> EndOfDay {
+        self.window = window;
+        self
+    }
+
+    /// Flattens through `policy` instead of the default TouchPolicy. Give it the same clock as
+    /// this EndOfDay, if it reads one.
+    pub fn policy<P: ExecutionPolicy + 'static>( mut self, policy: P ) -> EndOfDay {
+        self.policy = Box::new( policy );
+        self.default_policy = false;
+        self
+    }
+
+    /// Reads the time through `clock` rather than the system clock (the default policy too).
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> EndOfDay {
+        if self.default_policy {
+            self.policy = Box::new( TouchPolicy::new( Qty( 100 ) ).with_clock( clock.clone() ) );
+        }
+        self.clock = clock;
+        self
+    }
+
+    /// Whether the close has passed.
+    pub fn is_closing( &self ) -> bool {
+        self.clock.wall() >= self.close_at
+    }
+
+    /// True the first time it's called after the close, and never again -- the moment to pull
+    /// queued new orders and cancel resting ones.
+    pub fn just_closed( &mut self ) -> bool {
+        if self.announced || !self.is_closing() {
+            return false;
+        }
+        self.announced = true;
+        true
+    }
+
+    /// Whether `order` may go out with the book at net `position`. Before the close, anything
+    /// may; after it, only orders that shrink the position without flipping it.
+    pub fn allows( &self, order: &Order, position: i32 ) -> bool {
+        !self.is_closing() || shrinks( &order.direction, order.qty, position )
+    }
+
+    /// Whether the unfilled part of `order` would shrink `position` without flipping it --
+    /// resting orders that don't are the ones to cancel at the close.
+    pub fn reduces( &self, order: &OrderResponse, position: i32 ) -> bool {
+        shrinks( &order.direction, order.original_qty.saturating_sub( order.total_filled ), position )
+    }
+
+    /// The orders to send now to work `position` in `quote`'s symbol towards flat, given the
+    /// `open` orders already working. What those would flatten once filled is subtracted
+    /// first, so calling this every pass doesn't stack orders past flat. Empty before the
+    /// close and once flat.
+    pub fn orders( &mut self, account: &str, quote: &Quote, position: i32, open: &[OrderResponse] ) -> Vec<Order> {
+        if !self.is_closing() || position == 0 {
+            return vec![];
+        }
+        let selling = position > 0;
+        let working: i64 = open.iter()
+            .filter( |order| order.open && order.venue == quote.venue && order.symbol == quote.symbol )
+            .filter( |order| ( order.direction == "sell" ) == selling )
+            .map( |order| order.original_qty.saturating_sub( order.total_filled ).get() as i64 )
+            .sum();
+        // What's left to flatten, once the working orders fill
+        let remaining = if selling {
+            ( position as i64 - working ).max( 0 )
+        } else {
+            ( position as i64 + working ).min( 0 )
+        } as i32;
+        if remaining == 0 {
+            return vec![];
+        }
+        let venue = quote.venue.clone();
+        let stock = quote.symbol.clone();
+        let deadline = self.close_at + self.window;
+        // Judge the window here, by our clock, so a policy on another clock still crosses in time
+        let intent = if self.clock.wall() < deadline {
+            Intent::TargetPositionBy { venue: venue, stock: stock, target: 0, deadline: deadline }
+        } else {
+            Intent::TargetPosition { venue: venue, stock: stock, target: 0 }
+        };
+        self.policy.orders( &intent, account, quote, remaining )
+    }
+}
+
+fn shrinks( direction: &str, qty: Qty, position: i32 ) -> bool {
+    let qty = qty.get() as i64;
+    let position = position as i64;
+    match direction {
+        "buy" => position < 0 && qty <= -position,
+        "sell" => position > 0 && qty <= position,
+        _ => false,
+    }
+}
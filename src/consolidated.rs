@@ -0,0 +1,208 @@
+// A single view over the same symbol's books on several venues.
+//
+// Multi-venue levels trade one stock in more than one place. ConsolidatedBook merges the
+// individual OrderBooks into one price ladder per side -- the best bid/offer across all
+// venues sits at the top -- while keeping track of how much of each level lives on which
+// venue, so you still know where to send an order.
+//
+// No single venue lets its own book lock or cross, but nothing stops one venue's bid from
+// meeting or passing another venue's offer. `dislocation` spots that -- it's free money for
+// whoever sends the pair of orders first -- and DislocationWatch reports each new one as it
+// appears.
+
+use std::collections::BTreeMap;
+
+use {OrderBook, Qty};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VenueSize {
+    pub venue: String,
+    pub qty: Qty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsolidatedLevel {
+    pub price: i32,
+    /// Total across every venue
+    pub qty: Qty,
+    pub venues: Vec<VenueSize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConsolidatedBook {
+    pub symbol: String,
+    /// Best (highest) price first
+    pub bids: Vec<ConsolidatedLevel>,
+    /// Best (lowest) price first
+    pub asks: Vec<ConsolidatedLevel>,
+}
+
+// price -> venue -> qty, BTreeMaps so the output comes out sorted
+type Ladder = BTreeMap<i32, BTreeMap<String, Qty>>;
+
+fn flatten( ladder: Ladder ) -> Vec<ConsolidatedLevel> {
+    ladder.into_iter().map( |( price, venues )| {
+        ConsolidatedLevel {
+            price: price,
+            qty: venues.values().fold( Qty( 0 ), |total, &qty| total.saturating_add( qty ) ),
+            venues: venues.into_iter()
+                          .map( |( venue, qty )| VenueSize { venue: venue, qty: qty } )
+                          .collect(),
+        }
+    }).collect()
+}
+
+impl ConsolidatedBook {
+    /// Merges `books`. Books for a different symbol than the first one are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// let mut books = vec![];
+    /// for venue in &[ "TESTEX", "OBEX" ] {
+    ///   let mut book = market::OrderBook::new( venue.to_string(), "FOOBAR".to_owned() );
+    ///   book.refresh().unwrap();
+    ///   books.push( book );
+    /// }
+    /// let consolidated = market::consolidated::ConsolidatedBook::from_books( &books );
+    /// if let Some( best ) = consolidated.best_bid() {
+    ///   println!("best bid {} for {} shares, on {:?}", best.price, best.qty, best.venues );
+    /// }
+    /// ```
+    pub fn from_books( books: &[OrderBook] ) -> ConsolidatedBook {
+        let symbol = books.first().map_or( String::new(), |book| book.symbol.clone() );
+        let mut bids = Ladder::new();
+        let mut asks = Ladder::new();
+        for book in books.iter().filter( |book| book.symbol == symbol ) {
+            for level in &book.bids {
+                let qty = bids.entry( level.price ).or_insert_with( BTreeMap::new )
+                              .entry( book.venue.clone() ).or_insert( Qty( 0 ) );
+                *qty = qty.saturating_add( level.qty );
+            }
+            for level in &book.asks {
+                let qty = asks.entry( level.price ).or_insert_with( BTreeMap::new )
+                              .entry( book.venue.clone() ).or_insert( Qty( 0 ) );
+                *qty = qty.saturating_add( level.qty );
+            }
+        }
+        let mut bids = flatten( bids );
+        bids.reverse();
+        ConsolidatedBook {
+            symbol: symbol,
+            bids: bids,
+            asks: flatten( asks ),
+        }
+    }
+
+    pub fn best_bid( &self ) -> Option<&ConsolidatedLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask( &self ) -> Option<&ConsolidatedLevel> {
+        self.asks.first()
+    }
+
+    /// The best bid and offer prices across every venue.
+    pub fn nbbo( &self ) -> ( Option<i32>, Option<i32> ) {
+        ( self.best_bid().map( |level| level.price ), self.best_ask().map( |level| level.price ) )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dislocation {
+    /// The best bid on one venue equals the best offer on another
+    Locked,
+    /// The best bid on one venue is above the best offer on another
+    Crossed,
+}
+
+/// A locked or crossed consolidated market, and who's in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossedMarket {
+    pub kind: Dislocation,
+    pub bid: i32,
+    pub ask: i32,
+    /// Where the best bid rests
+    pub bid_venues: Vec<VenueSize>,
+    /// Where the best offer rests
+    pub ask_venues: Vec<VenueSize>,
+    /// Shares that could be bought and immediately sold at no loss: the smaller of the size
+    /// bid at or above the best offer and the size offered at or below the best bid
+    pub qty: Qty,
+}
+
+impl ConsolidatedBook {
+    /// The locked or crossed market across venues, if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// let client = market::StockfighterClient::new();
+    /// let books = vec![ client.order_book( "TESTEX", "FOOBAR" ).unwrap(),
+    ///                   client.order_book( "OBEX", "FOOBAR" ).unwrap() ];
+    /// let consolidated = market::consolidated::ConsolidatedBook::from_books( &books );
+    /// if let Some( crossed ) = consolidated.dislocation() {
+    ///   println!("{:?}: buy {} on {:?}, sell {} on {:?}", crossed.kind, crossed.qty,
+    ///            crossed.ask_venues, crossed.qty, crossed.bid_venues );
+    /// }
+    /// ```
+    pub fn dislocation( &self ) -> Option<CrossedMarket> {
+        let ( best_bid, best_ask ) = match ( self.best_bid(), self.best_ask() ) {
+            ( Some( bid ), Some( ask ) ) => ( bid, ask ),
+            _ => return None,
+        };
+        let kind = if best_bid.price > best_ask.price {
+            Dislocation::Crossed
+        } else if best_bid.price == best_ask.price {
+            Dislocation::Locked
+        } else {
+            return None;
+        };
+        let bid_size = self.bids.iter()
+                           .take_while( |level| level.price >= best_ask.price )
+                           .fold( Qty( 0 ), |total, level| total.saturating_add( level.qty ) );
+        let ask_size = self.asks.iter()
+                           .take_while( |level| level.price <= best_bid.price )
+                           .fold( Qty( 0 ), |total, level| total.saturating_add( level.qty ) );
+        Some( CrossedMarket {
+            kind: kind,
+            bid: best_bid.price,
+            ask: best_ask.price,
+            bid_venues: best_bid.venues.clone(),
+            ask_venues: best_ask.venues.clone(),
+            qty: bid_size.min( ask_size ),
+        })
+    }
+}
+
+/// Calls back once for each new locked or crossed market -- when one appears, or its prices
+/// change -- rather than on every check while it persists.
+pub struct DislocationWatch {
+    on_dislocation: Option<Box<FnMut( &str, &CrossedMarket )>>,
+    last: Option<(Dislocation, i32, i32)>,
+}
+
+impl DislocationWatch {
+    pub fn new() -> DislocationWatch {
+        DislocationWatch { on_dislocation: None, last: None }
+    }
+
+    /// Called with the symbol and the dislocation.
+    pub fn on_dislocation<F: FnMut( &str, &CrossedMarket ) + 'static>( mut self, callback: F ) -> DislocationWatch {
+        self.on_dislocation = Some( Box::new( callback ) );
+        self
+    }
+
+    /// Checks `book`, returning the dislocation if there is one.
+    pub fn check( &mut self, book: &ConsolidatedBook ) -> Option<CrossedMarket> {
+        let found = book.dislocation();
+        let key = found.as_ref().map( |crossed| ( crossed.kind, crossed.bid, crossed.ask ) );
+        if key != self.last {
+            if let Some( ref crossed ) = found {
+                if let Some( ref mut callback ) = self.on_dislocation {
+                    callback( &book.symbol, crossed );
+                }
+            }
+            self.last = key;
+        }
+        found
+    }
+}
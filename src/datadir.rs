@@ -0,0 +1,83 @@
+// Where a run's files go.
+//
+// Recordings, journals, blotter dumps and reports from different attempts at a level used to
+// land wherever each example put them, so the second attempt overwrote the first. DataDir
+// gives every GM instance its own directory under one root -- `data/<instance>/` by default,
+// or `$SF_DATA_DIR/<instance>/` -- and hands out paths and open files inside it.
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+#[cfg(feature = "analytics")]
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use StockfighterErr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDir {
+    dir: PathBuf,
+}
+
+// Instance ids come from the GM and end up in a path; keep them to one harmless component
+fn component( instance: &str ) -> String {
+    let cleaned: String = instance.chars().map( |c| {
+        if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }
+    }).collect();
+    if cleaned.is_empty() { "unnamed".to_owned() } else { cleaned }
+}
+
+impl DataDir {
+    /// The directory for `instance` under `$SF_DATA_DIR`, or `./data` if that isn't set.
+    ///
+    /// # Example
+    /// ```
+    /// use market::datadir::DataDir;
+    /// let dir = DataDir::new( "1234" );
+    /// let quotes = dir.path( "quotes.jsonl" ).unwrap();
+    /// assert!( quotes.ends_with( "1234/quotes.jsonl" ) );
+    /// ```
+    pub fn new( instance: &str ) -> DataDir {
+        let root = env::var( "SF_DATA_DIR" ).unwrap_or( "data".to_owned() );
+        DataDir::under( Path::new( &root ), instance )
+    }
+
+    /// The directory for `instance` under `root`.
+    pub fn under( root: &Path, instance: &str ) -> DataDir {
+        DataDir { dir: root.join( component( instance ) ) }
+    }
+
+    /// The instance's directory. It may not exist until the first `path` call.
+    pub fn dir( &self ) -> &Path {
+        &self.dir
+    }
+
+    /// `name` inside the instance's directory, creating the directory if needed.
+    pub fn path( &self, name: &str ) -> Result<PathBuf, StockfighterErr> {
+        try!( fs::create_dir_all( &self.dir ) );
+        Ok( self.dir.join( name ) )
+    }
+
+    /// Creates (or truncates) `name`.
+    pub fn create( &self, name: &str ) -> Result<File, StockfighterErr> {
+        Ok( try!( File::create( try!( self.path( name ) ) ) ) )
+    }
+
+    /// Opens `name` for appending, creating it if needed -- for journals and logs that should
+    /// survive a restart of the same attempt.
+    pub fn append( &self, name: &str ) -> Result<File, StockfighterErr> {
+        let path = try!( self.path( name ) );
+        Ok( try!( OpenOptions::new().create( true ).append( true ).open( path ) ) )
+    }
+
+    /// A recording file (see recording::create; compressed if `name` ends in .gz).
+    #[cfg(feature = "analytics")]
+    pub fn recording( &self, name: &str ) -> Result<Box<Write + Send>, StockfighterErr> {
+        ::recording::create( &try!( self.path( name ) ) )
+    }
+
+    /// A journal appending to `journal.jsonl`.
+    #[cfg(feature = "strategy")]
+    pub fn journal( &self ) -> Result<::journal::Journal, StockfighterErr> {
+        Ok( ::journal::Journal::to_writer( try!( self.append( "journal.jsonl" ) ) ) )
+    }
+}
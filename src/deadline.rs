@@ -0,0 +1,111 @@
+// Latency budgets for order submission.
+//
+// On a time-critical level, an order that hasn't been acknowledged in 200ms may as well not
+// have been sent -- the strategy would rather know now and adjust than sit blocked in
+// process_order. `process_order_within` gives up waiting once the budget is spent and hands
+// back an InFlight handle instead of the response. The request itself can't be recalled, so it
+// keeps going in the background; the handle lets the caller pick up how it actually turned out.
+//
+// The outcome is a Submitted: Acked with the response, or Late with the handle. The handle
+// travels there rather than inside StockfighterErr, so the error type stays plain data that can
+// be shared between threads. Callers who only care that it was late use `Submitted::acked`,
+// which turns Late into StockfighterErr::DeadlineExceeded.
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use {Auth, Order, OrderResponse, StockfighterErr};
+
+/// An order submission that is still running in the background.
+#[derive(Debug)]
+pub struct InFlight {
+    rx: Receiver<Result<OrderResponse, StockfighterErr>>,
+}
+
+impl InFlight {
+    /// Blocks until the submission finishes.
+    pub fn wait( self ) -> Result<OrderResponse, StockfighterErr> {
+        match self.rx.recv() {
+            Ok( result ) => result,
+            Err( _ ) => Err( StockfighterErr::WorkerDied ),
+        }
+    }
+
+    /// The outcome if the submission has finished, None if it's still going.
+    pub fn poll( &self ) -> Option<Result<OrderResponse, StockfighterErr>> {
+        match self.rx.try_recv() {
+            Ok( result ) => Some( result ),
+            Err( TryRecvError::Empty ) => None,
+            Err( TryRecvError::Disconnected ) => Some( Err( StockfighterErr::WorkerDied ) ),
+        }
+    }
+}
+
+/// How a submission with a latency budget turned out, if it didn't fail outright.
+#[derive(Debug)]
+pub enum Submitted {
+    /// Acknowledged within the budget
+    Acked( OrderResponse ),
+    /// Still going when the budget ran out
+    Late( InFlight ),
+}
+
+impl Submitted {
+    /// The response, or DeadlineExceeded if it was late. A late order may still go through;
+    /// this gives up the handle that would tell you.
+    pub fn acked( self ) -> Result<OrderResponse, StockfighterErr> {
+        match self {
+            Submitted::Acked( response ) => Ok( response ),
+            Submitted::Late( _ ) => Err( StockfighterErr::DeadlineExceeded ),
+        }
+    }
+}
+
+impl Order {
+    /// Sends the order, but only waits `budget` for the server to acknowledge it. Gives
+    /// `Ok( Acked( response ) )` if it was acknowledged in time, `Ok( Late( in_flight ) )` if
+    /// not, and `Err` if it failed within the budget.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use market::deadline::Submitted;
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// match order.process_order_within( Duration::from_millis( 200 ) ) {
+    ///   Ok( Submitted::Acked( response ) ) => println!("acked as order {}", response.id ),
+    ///   Ok( Submitted::Late( in_flight ) ) => {
+    ///     println!("too slow -- requoting, and checking on the order later");
+    ///     let outcome = in_flight.wait();
+    ///     println!("it did turn out: {:?}", outcome.is_ok() );
+    ///   },
+    ///   Err( e ) => println!("order failed: {}", e ),
+    /// }
+    ///
+    /// // Or, treating lateness as just another error:
+    /// let acked = order.process_order_within( Duration::from_millis( 200 ) )
+    ///                  .and_then( Submitted::acked );
+    /// ```
+    pub fn process_order_within( &self, budget: Duration ) -> Result<Submitted, StockfighterErr> {
+        self.process_order_within_with( budget, &Auth::Env )
+    }
+
+    /// process_order_within, authenticating with `auth`.
+    pub fn process_order_within_with( &self, budget: Duration, auth: &Auth )
+                                      -> Result<Submitted, StockfighterErr> {
+        let ( tx, rx ) = mpsc::channel();
+        let order = self.clone();
+        let auth = auth.clone();
+        thread::spawn( move || {
+            // Nobody listening any more is fine -- the caller dropped the InFlight handle
+            let _ = tx.send( order.process_order_with( &auth ) );
+        });
+        match rx.recv_timeout( budget ) {
+            Ok( result ) => result.map( Submitted::Acked ),
+            Err( RecvTimeoutError::Timeout ) => Ok( Submitted::Late( InFlight { rx: rx } ) ),
+            Err( RecvTimeoutError::Disconnected ) => Err( StockfighterErr::WorkerDied ),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Turning response bodies into structs.
+//
+// Not every response is JSON. A connection cut short leaves an empty body, and a proxy or a
+// struggling server will happily hand back an HTML error page. Handing either straight to
+// serde gets you a syntax error that says nothing about what actually came back, so bodies
+// are checked here first and turned into EmptyBody / NotJson errors that do.
+//
+// Most responses also carry `ok` (and usually `error`). A request the server refused still
+// deserializes fine, into a struct of default zeros that looks perfectly plausible. `checked`
+// catches `ok: false` before the struct is built and turns it into StockfighterErr::Api
+// carrying the server's error message instead.
+
+use serde;
+use serde_json;
+
+use StockfighterErr;
+
+// How much of a non-JSON body to keep in the error -- enough to recognise an error page
+static SNIPPET_LEN: usize = 200;
+
+fn check( body: &str ) -> Result<(), StockfighterErr> {
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        return Err( StockfighterErr::EmptyBody );
+    }
+    if !trimmed.starts_with( '{' ) && !trimmed.starts_with( '[' ) {
+        let snippet: String = trimmed.chars().take( SNIPPET_LEN ).collect();
+        return Err( StockfighterErr::NotJson( snippet ) );
+    }
+    Ok( () )
+}
+
+/// Parses `body` as `T`.
+pub fn decode<T: serde::Deserialize>( body: &str ) -> Result<T, StockfighterErr> {
+    try!( check( body ) );
+    let value = try!( serde_json::from_str( body ) );
+    Ok( value )
+}
+
+/// Parses `body` as `T`, unless the server said `ok: false`, which becomes an Api error.
+///
+/// # Example
+/// ```
+/// use market::decode::checked;
+/// let refused = r#"{"ok":false,"error":"No venue exists with the symbol BADEX"}"#;
+/// match checked::<market::Quote>( refused ) {
+///   Err( market::StockfighterErr::Api( message ) ) => println!("refused: {}", message ),
+///   _ => unreachable!(),
+/// }
+///
+/// // Truncated, mistyped or non-JSON bodies are errors too, never panics
+/// let garbage = [ "", " ", "null", "[]", "{", r#"{"ok":"#, r#"{"ok":1}"#, r#"{"ok":false}"#,
+///                 "<html>502 Bad Gateway</html>", "\u{0}" ];
+/// for body in garbage.iter() {
+///   assert!( checked::<market::Quote>( body ).is_err() );
+/// }
+/// ```
+pub fn checked<T: serde::Deserialize>( body: &str ) -> Result<T, StockfighterErr> {
+    let value: serde_json::Value = try!( decode( body ) );
+    let refused = value.as_object()
+                       .and_then( |object| object.get( "ok" ) )
+                       .and_then( |ok| ok.as_bool() ) == Some( false );
+    if refused {
+        let message = value.as_object()
+                           .and_then( |object| object.get( "error" ) )
+                           .and_then( |error| error.as_str() )
+                           .unwrap_or( "" )
+                           .to_owned();
+        return Err( StockfighterErr::Api( message ) );
+    }
+    let typed = try!( serde_json::from_value( value ) );
+    Ok( typed )
+}
@@ -0,0 +1,93 @@
+// Cumulative depth curves -- the data behind the usual "depth chart" (price on the x axis,
+// total size available up to that price on the y axis, one curve per side).
+//
+// The curves are plain vectors so they can go straight into whichever plotting crate you like.
+// `sparkline` squeezes the same thing into a line of ASCII for terminal use.
+
+use OrderBook;
+
+// Darkest last. Plain ASCII so it survives any terminal or log file
+static RAMP: &'static [u8] = b" .:-=+*#%@";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepthCurve {
+    /// (price, cumulative qty), from the best bid downwards
+    pub bids: Vec<(i32, u32)>,
+    /// (price, cumulative qty), from the best ask upwards
+    pub asks: Vec<(i32, u32)>,
+}
+
+// Collapses levels to one entry per price (in `order`) with running totals
+fn cumulate( mut levels: Vec<(i32, u32)>, descending: bool ) -> Vec<(i32, u32)> {
+    if descending {
+        levels.sort_by( |a, b| b.0.cmp( &a.0 ) );
+    } else {
+        levels.sort_by( |a, b| a.0.cmp( &b.0 ) );
+    }
+    let mut curve: Vec<(i32, u32)> = vec![];
+    let mut total: u32 = 0;
+    for ( price, qty ) in levels {
+        total = total.saturating_add( qty );
+        let same_price = curve.last().map_or( false, |&( last_price, _ )| last_price == price );
+        if same_price {
+            let last = curve.len() - 1;
+            curve[last].1 = total;
+        } else {
+            curve.push( ( price, total ) );
+        }
+    }
+    curve
+}
+
+impl OrderBook {
+    /// Cumulative depth for each side of the book.
+    ///
+    /// # Example
+    /// ```
+    /// let mut book = market::OrderBook::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
+    /// book.refresh().unwrap();
+    /// let curve = book.depth_curve();
+    /// for &( price, total ) in &curve.bids {
+    ///   println!("{} shares bid at {} or better", total, price );
+    /// }
+    /// println!("[{}]", curve.sparkline( 60 ) );
+    /// ```
+    pub fn depth_curve( &self ) -> DepthCurve {
+        DepthCurve {
+            bids: cumulate( self.bids.iter().map( |level| ( level.price, level.qty.get() ) ).collect(), true ),
+            asks: cumulate( self.asks.iter().map( |level| ( level.price, level.qty.get() ) ).collect(), false ),
+        }
+    }
+}
+
+impl DepthCurve {
+    /// One character per price level, deepest bid on the left through to deepest ask on the
+    /// right with a `|` at the spread. Darker characters mean more cumulative size. At most
+    /// `width` characters (split evenly between the sides) are produced.
+    pub fn sparkline( &self, width: usize ) -> String {
+        let per_side = width.saturating_sub( 1 ) / 2;
+        let max = self.bids.iter().take( per_side )
+                      .chain( self.asks.iter().take( per_side ) )
+                      .map( |&( _, total )| total )
+                      .max()
+                      .unwrap_or( 0 );
+        let shade = |total: u32| -> char {
+            if max == 0 {
+                return ' ';
+            }
+            let index = ( total as u64 * ( RAMP.len() - 1 ) as u64 / max as u64 ) as usize;
+            RAMP[index] as char
+        };
+
+        let mut line = String::new();
+        let bids: Vec<u32> = self.bids.iter().take( per_side ).map( |&( _, total )| total ).collect();
+        for total in bids.into_iter().rev() {
+            line.push( shade( total ) );
+        }
+        line.push( '|' );
+        for &( _, total ) in self.asks.iter().take( per_side ) {
+            line.push( shade( total ) );
+        }
+        line
+    }
+}
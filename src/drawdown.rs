@@ -0,0 +1,193 @@
+// Drawdown and trade statistics.
+//
+// P&L on its own says where a strategy ended up, not how rough the ride was. Drawdown follows
+// the marked P&L (see mark::Marking::total) and keeps the running peak, the worst fall from
+// it, and how long the strategy has spent below the peak. Give it a limit and it trips once
+// the drawdown goes past it, so the bot can stop quoting before a bad afternoon becomes a bad
+// level. TradeStats keeps the win/loss record of closed trades.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clock::{Clock, SystemClock};
+
+pub struct Drawdown {
+    peak: Option<i64>,
+    current: i64,
+    max: i64,
+    under_water_since: Option<Instant>,
+    longest_under_water: Duration,
+    limit: Option<i64>,
+    tripped: bool,
+    on_breach: Option<Box<FnMut( i64 )>>,
+    clock: Arc<Clock>,
+}
+
+impl Drawdown {
+    pub fn new() -> Drawdown {
+        Drawdown {
+            peak: None,
+            current: 0,
+            max: 0,
+            under_water_since: None,
+            longest_under_water: Duration::from_secs( 0 ),
+            limit: None,
+            tripped: false,
+            on_breach: None,
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    /// Trips once the drawdown exceeds `cents`.
+    pub fn limit( mut self, cents: i64 ) -> Drawdown {
+        self.limit = Some( cents );
+        self
+    }
+
+    /// Called once, with the drawdown at the time, when the limit is first exceeded.
+    pub fn on_breach<F: FnMut( i64 ) + 'static>( mut self, callback: F ) -> Drawdown {
+        self.on_breach = Some( Box::new( callback ) );
+        self
+    }
+
+    /// Measures time under water with `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> Drawdown {
+        self.clock = clock;
+        self
+    }
+
+    /// Feeds in the latest P&L, in cents. Returns true if this update tripped the limit.
+    ///
+    /// # Example
+    /// ```
+    /// use market::drawdown::Drawdown;
+    /// let mut drawdown = Drawdown::new()
+    ///                        .limit( 50000 )
+    ///                        .on_breach( |cents| println!("down ${:.2} from peak, pulling quotes", cents as f64 / 100.0 ) );
+    /// drawdown.update( 10000 );
+    /// drawdown.update( 60000 );
+    /// assert!( drawdown.update( 5000 ) );
+    /// assert_eq!( drawdown.max(), 55000 );
+    /// assert!( drawdown.tripped() );
+    /// ```
+    pub fn update( &mut self, pnl: i64 ) -> bool {
+        let now = self.clock.now();
+        let peak = match self.peak {
+            Some( peak ) if peak >= pnl => peak,
+            _ => {
+                self.peak = Some( pnl );
+                self.under_water_since = None;
+                pnl
+            },
+        };
+        self.current = peak - pnl;
+        if self.current > 0 && self.under_water_since.is_none() {
+            self.under_water_since = Some( now );
+        }
+        let under_water = self.under_water();
+        if under_water > self.longest_under_water {
+            self.longest_under_water = under_water;
+        }
+        if self.current > self.max {
+            self.max = self.current;
+        }
+        match self.limit {
+            Some( limit ) if !self.tripped && self.current > limit => {
+                self.tripped = true;
+                let current = self.current;
+                if let Some( ref mut on_breach ) = self.on_breach {
+                    on_breach( current );
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// How far below the peak the last update was, in cents.
+    pub fn current( &self ) -> i64 {
+        self.current
+    }
+
+    /// The worst drawdown seen, in cents.
+    pub fn max( &self ) -> i64 {
+        self.max
+    }
+
+    /// How long P&L has been below its peak, zero if it's at the peak.
+    pub fn under_water( &self ) -> Duration {
+        match self.under_water_since {
+            Some( since ) => self.clock.now() - since,
+            None => Duration::from_secs( 0 ),
+        }
+    }
+
+    /// The longest stretch spent below the peak, as of the last update.
+    pub fn longest_under_water( &self ) -> Duration {
+        self.longest_under_water
+    }
+
+    /// Whether the limit has been exceeded. Stays set until `reset`.
+    pub fn tripped( &self ) -> bool {
+        self.tripped
+    }
+
+    /// Re-arms the limit, and starts the peak over from the next update.
+    pub fn reset( &mut self ) {
+        self.tripped = false;
+        self.peak = None;
+        self.current = 0;
+        self.under_water_since = None;
+    }
+}
+
+/// Win/loss record over closed trades.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradeStats {
+    pub wins: usize,
+    pub losses: usize,
+    /// Total won on winning trades, in cents
+    pub won: i64,
+    /// Total lost on losing trades, in cents (positive)
+    pub lost: i64,
+    /// Positive for a run of wins, negative for a run of losses
+    pub streak: i32,
+    pub longest_winning_streak: u32,
+    pub longest_losing_streak: u32,
+}
+
+impl TradeStats {
+    pub fn new() -> TradeStats {
+        TradeStats::default()
+    }
+
+    /// Records a closed trade's P&L in cents. A scratch (zero) counts as neither and leaves
+    /// the streak alone.
+    pub fn record( &mut self, pnl: i64 ) {
+        if pnl > 0 {
+            self.wins += 1;
+            self.won += pnl;
+            self.streak = if self.streak > 0 { self.streak + 1 } else { 1 };
+            self.longest_winning_streak = self.longest_winning_streak.max( self.streak as u32 );
+        } else if pnl < 0 {
+            self.losses += 1;
+            self.lost -= pnl;
+            self.streak = if self.streak < 0 { self.streak - 1 } else { -1 };
+            self.longest_losing_streak = self.longest_losing_streak.max( ( -self.streak ) as u32 );
+        }
+    }
+
+    /// Fraction of decided trades that won, None before any.
+    pub fn win_rate( &self ) -> Option<f64> {
+        let decided = self.wins + self.losses;
+        if decided == 0 { None } else { Some( self.wins as f64 / decided as f64 ) }
+    }
+
+    /// Average win over average loss, None until there's at least one of each.
+    pub fn payoff_ratio( &self ) -> Option<f64> {
+        if self.wins == 0 || self.losses == 0 || self.lost == 0 {
+            return None;
+        }
+        Some( ( self.won as f64 / self.wins as f64 ) / ( self.lost as f64 / self.losses as f64 ) )
+    }
+}
@@ -0,0 +1,108 @@
+// One event type for everything that comes out of the market.
+//
+// The recorder writes quotes, the tape keeps book deltas, the blotter ingests fills, the
+// anomaly detector reports anomalies -- each in its own type, so wiring any two of them
+// together meant an adapter. MarketEvent wraps them all. It serializes to one JSON object per
+// event (`{"Quote":{...}}`, `{"Fill":{...}}`); recording::SequencedWriter::write_event and the
+// recorder example write streams of them, and recording::replay feeds a recording back to a
+// handler. The tape, blotter and anomaly detector still take their own types -- a handler
+// matches on the variant and passes the inner value along.
+
+use {OrderFill, OrderResponse, Qty, Quote};
+use anomaly::Anomaly;
+use tape::BookDelta;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum MarketEvent {
+    Quote( Quote ),
+    /// A trade printed on the venue (anyone's, not necessarily ours)
+    Trade { venue: String, symbol: String, price: i32, qty: Qty, ts: String },
+    BookUpdate { venue: String, symbol: String, delta: BookDelta },
+    /// One of our orders traded
+    Fill { venue: String, symbol: String, order_id: i32, direction: String, fill: OrderFill },
+    /// The venue has one of our orders
+    OrderAck( OrderResponse ),
+    Heartbeat { venue: String, ok: bool },
+    Anomaly { venue: String, symbol: String, description: String },
+    /// Anything else, as a kind and a JSON payload
+    Custom { kind: String, payload: String },
+}
+
+impl MarketEvent {
+    /// The trade a quote reports as its last, if it has one.
+    ///
+    /// # Example
+    /// ```
+    /// use market::event::MarketEvent;
+    /// let mut quote = market::Quote::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
+    /// quote.last = 4550;
+    /// quote.last_size = market::Qty( 20 );
+    /// quote.last_trade = "2015-12-04T09:02:16.680986205Z".to_owned();
+    /// let trade = MarketEvent::trade_from( &quote ).unwrap();
+    /// assert_eq!( trade.kind(), "trade" );
+    /// assert_eq!( trade.symbol(), Some( "FOOBAR" ) );
+    /// ```
+    pub fn trade_from( quote: &Quote ) -> Option<MarketEvent> {
+        if quote.last_trade.is_empty() || quote.last <= 0 {
+            return None;
+        }
+        Some( MarketEvent::Trade {
+            venue: quote.venue.clone(),
+            symbol: quote.symbol.clone(),
+            price: quote.last,
+            qty: quote.last_size,
+            ts: quote.last_trade.clone(),
+        })
+    }
+
+    /// An anomaly the detector found in `quote`.
+    pub fn anomaly( quote: &Quote, anomaly: &Anomaly ) -> MarketEvent {
+        MarketEvent::Anomaly {
+            venue: quote.venue.clone(),
+            symbol: quote.symbol.clone(),
+            description: format!("{:?}", anomaly),
+        }
+    }
+
+    /// A short name for the variant: quote, trade, book_update, fill, order_ack, heartbeat,
+    /// anomaly, or the kind of a custom event.
+    pub fn kind( &self ) -> &str {
+        match *self {
+            MarketEvent::Quote( _ ) => "quote",
+            MarketEvent::Trade { .. } => "trade",
+            MarketEvent::BookUpdate { .. } => "book_update",
+            MarketEvent::Fill { .. } => "fill",
+            MarketEvent::OrderAck( _ ) => "order_ack",
+            MarketEvent::Heartbeat { .. } => "heartbeat",
+            MarketEvent::Anomaly { .. } => "anomaly",
+            MarketEvent::Custom { ref kind, .. } => kind,
+        }
+    }
+
+    /// The venue the event came from, if it has one.
+    pub fn venue( &self ) -> Option<&str> {
+        match *self {
+            MarketEvent::Quote( ref quote ) => Some( quote.venue.as_str() ),
+            MarketEvent::OrderAck( ref order ) => Some( order.venue.as_str() ),
+            MarketEvent::Trade { ref venue, .. } |
+            MarketEvent::BookUpdate { ref venue, .. } |
+            MarketEvent::Fill { ref venue, .. } |
+            MarketEvent::Heartbeat { ref venue, .. } |
+            MarketEvent::Anomaly { ref venue, .. } => Some( venue.as_str() ),
+            MarketEvent::Custom { .. } => None,
+        }
+    }
+
+    /// The symbol the event is about, if it's about one.
+    pub fn symbol( &self ) -> Option<&str> {
+        match *self {
+            MarketEvent::Quote( ref quote ) => Some( quote.symbol.as_str() ),
+            MarketEvent::OrderAck( ref order ) => Some( order.symbol.as_str() ),
+            MarketEvent::Trade { ref symbol, .. } |
+            MarketEvent::BookUpdate { ref symbol, .. } |
+            MarketEvent::Fill { ref symbol, .. } |
+            MarketEvent::Anomaly { ref symbol, .. } => Some( symbol.as_str() ),
+            MarketEvent::Heartbeat { .. } | MarketEvent::Custom { .. } => None,
+        }
+    }
+}
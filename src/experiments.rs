@@ -0,0 +1,157 @@
+// Parameter sweeps.
+//
+// Tuning a strategy usually means running it over the same recording again and again with a
+// different spread or size each time and copying numbers into a spreadsheet. Grid spells out
+// the values to try, Experiment runs a closure once per combination and collects whatever
+// metrics it reports (P&L, max drawdown, fills...), and Results prints them side by side.
+//
+// The closure does the actual run -- typically opening a recording (recording::open) and
+// feeding it through the strategy -- so this works with any strategy shape. A run that fails
+// is kept in the table with its error rather than aborting the sweep.
+
+use std::collections::BTreeMap;
+
+use StockfighterErr;
+
+pub type Params = BTreeMap<String, i64>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Grid {
+    axes: Vec<(String, Vec<i64>)>,
+}
+
+impl Grid {
+    pub fn new() -> Grid {
+        Grid::default()
+    }
+
+    /// Adds a parameter and the values to try for it.
+    pub fn param( mut self, name: &str, values: &[i64] ) -> Grid {
+        self.axes.push( ( name.to_owned(), values.to_vec() ) );
+        self
+    }
+
+    /// Every combination of parameter values, with the last parameter varying fastest.
+    pub fn combinations( &self ) -> Vec<Params> {
+        let mut out = vec![ Params::new() ];
+        for &( ref name, ref values ) in &self.axes {
+            let mut next = Vec::with_capacity( out.len() * values.len() );
+            for params in &out {
+                for &value in values {
+                    let mut params = params.clone();
+                    params.insert( name.clone(), value );
+                    next.push( params );
+                }
+            }
+            out = next;
+        }
+        out
+    }
+}
+
+#[derive(Debug)]
+pub struct Run {
+    pub params: Params,
+    /// The metrics the run reported, by name, or why it failed
+    pub outcome: Result<BTreeMap<String, f64>, StockfighterErr>,
+}
+
+pub struct Experiment {
+    grid: Grid,
+}
+
+impl Experiment {
+    /// # Example
+    /// ```
+    /// use market::experiments::{Experiment, Grid};
+    /// let results = Experiment::new( Grid::new().param( "spread", &[5, 10, 20] )
+    ///                                           .param( "size", &[100, 500] ) )
+    ///                   .run( |params| {
+    ///                       // replay a recording with these settings here
+    ///                       let pnl = ( params["spread"] * params["size"] ) as f64;
+    ///                       Ok( vec![ ( "pnl", pnl ), ( "fills", 12.0 ) ] )
+    ///                   });
+    /// println!("{}", results.table() );
+    /// assert_eq!( results.best( "pnl" ).unwrap().params["spread"], 20 );
+    /// ```
+    pub fn new( grid: Grid ) -> Experiment {
+        Experiment { grid: grid }
+    }
+
+    /// Calls `run` once for every combination in the grid, in order.
+    pub fn run<F>( &self, mut run: F ) -> Results
+        where F: FnMut( &Params ) -> Result<Vec<( &'static str, f64 )>, StockfighterErr>
+    {
+        let runs = self.grid.combinations().into_iter().map( |params| {
+            let outcome = run( &params ).map( |metrics| {
+                metrics.into_iter().map( |( name, value )| ( name.to_owned(), value ) ).collect()
+            });
+            Run { params: params, outcome: outcome }
+        }).collect();
+        Results { runs: runs }
+    }
+}
+
+#[derive(Debug)]
+pub struct Results {
+    pub runs: Vec<Run>,
+}
+
+impl Results {
+    /// The successful run with the highest value of `metric`.
+    pub fn best( &self, metric: &str ) -> Option<&Run> {
+        let mut best: Option<( &Run, f64 )> = None;
+        for run in &self.runs {
+            if let Ok( ref metrics ) = run.outcome {
+                if let Some( &value ) = metrics.get( metric ) {
+                    if best.map( |( _, top )| value > top ).unwrap_or( true ) {
+                        best = Some( ( run, value ) );
+                    }
+                }
+            }
+        }
+        best.map( |( run, _ )| run )
+    }
+
+    /// One row per run: the parameters, then every metric any run reported.
+    pub fn table( &self ) -> String {
+        let params: Vec<&String> = match self.runs.first() {
+            Some( run ) => run.params.keys().collect(),
+            None => return String::new(),
+        };
+        let mut metrics: Vec<&String> = vec![];
+        for run in &self.runs {
+            if let Ok( ref reported ) = run.outcome {
+                for name in reported.keys() {
+                    if !metrics.contains( &name ) {
+                        metrics.push( name );
+                    }
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for name in params.iter().chain( metrics.iter() ) {
+            out.push_str( &format!("{:>12}", name) );
+        }
+        out.push( '\n' );
+        for run in &self.runs {
+            for name in &params {
+                out.push_str( &format!("{:>12}", run.params[ *name ]) );
+            }
+            match run.outcome {
+                Ok( ref reported ) => {
+                    for name in &metrics {
+                        match reported.get( *name ) {
+                            Some( value ) => out.push_str( &format!("{:>12.2}", value) ),
+                            None => out.push_str( &format!("{:>12}", "-") ),
+                        }
+                    }
+                },
+                Err( ref err ) => out.push_str( &format!("  failed: {}", err) ),
+            }
+            out.push( '\n' );
+        }
+        out
+    }
+}
@@ -0,0 +1,121 @@
+// Live quote export for dashboards.
+//
+// Grafana and friends already know how to chart a time series; all they need is the data.
+// QuoteExporter writes each quote as it arrives, one line per quote, either as CSV or as
+// InfluxDB line protocol, to anything that implements Write -- a file, a TcpStream to
+// Telegraf, stdout piped into whatever you like. Each line is flushed as it's written so the
+// chart keeps up with the level.
+
+use std::io::Write;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use {Quote, StockfighterErr};
+use clock::{Clock, SystemClock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Comma separated, with a header line first
+    Csv,
+    /// InfluxDB line protocol, measurement `quote`, tagged by venue and symbol
+    LineProtocol,
+}
+
+static CSV_HEADER: &'static str = "quote_time,venue,symbol,bid,ask,bid_size,ask_size,bid_depth,ask_depth,last,last_size";
+
+// CSV fields only need quoting if they contain a separator, a quote or a line break
+fn csv_field( field: &str ) -> String {
+    if field.contains( ',' ) || field.contains( '"' ) || field.contains( '\n' ) {
+        format!("\"{}\"", field.replace( "\"", "\"\"" ) )
+    } else {
+        field.to_owned()
+    }
+}
+
+// Line protocol tag values escape commas, spaces and equals signs with a backslash
+fn tag_value( value: &str ) -> String {
+    let mut escaped = String::with_capacity( value.len() );
+    for c in value.chars() {
+        if c == ',' || c == ' ' || c == '=' {
+            escaped.push( '\\' );
+        }
+        escaped.push( c );
+    }
+    escaped
+}
+
+pub struct QuoteExporter<W: Write> {
+    out: W,
+    format: Format,
+    wrote_header: bool,
+    clock: Arc<Clock>,
+}
+
+impl<W: Write> QuoteExporter<W> {
+    /// # Example
+    /// ```no_run
+    /// use std::net::TcpStream;
+    /// use market::export::{Format, QuoteExporter};
+    /// let telegraf = TcpStream::connect( "127.0.0.1:8094" ).unwrap();
+    /// let mut exporter = QuoteExporter::new( telegraf, Format::LineProtocol );
+    /// let client = market::StockfighterClient::new();
+    /// loop {
+    ///   exporter.write( &client.quote( "TESTEX", "FOOBAR" ).unwrap() ).unwrap();
+    /// }
+    /// ```
+    pub fn new( out: W, format: Format ) -> QuoteExporter<W> {
+        QuoteExporter { out: out, format: format, wrote_header: false, clock: Arc::new( SystemClock ) }
+    }
+
+    /// Stamps line protocol points with `clock`'s wall time rather than the system clock's.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> QuoteExporter<W> {
+        self.clock = clock;
+        self
+    }
+
+    /// Writes and flushes one line for `quote` (plus the header, before the first CSV line).
+    ///
+    /// # Example
+    /// ```
+    /// use market::export::{Format, QuoteExporter};
+    /// let quote: market::Quote = market::decode::decode( market::fixtures::QUOTE ).unwrap();
+    /// let mut exporter = QuoteExporter::new( vec![], Format::Csv );
+    /// exporter.write( &quote ).unwrap();
+    /// let csv = String::from_utf8( exporter.into_inner() ).unwrap();
+    /// assert_eq!( csv.lines().nth( 1 ).unwrap(),
+    ///             "2015-07-13T05:38:17.33640392Z,OGEX,FAC,5100,5125,392,711,2748,2237,5125,52" );
+    /// ```
+    pub fn write( &mut self, quote: &Quote ) -> Result<(), StockfighterErr> {
+        match self.format {
+            Format::Csv => {
+                if !self.wrote_header {
+                    try!( writeln!( self.out, "{}", CSV_HEADER ) );
+                    self.wrote_header = true;
+                }
+                try!( writeln!( self.out, "{},{},{},{},{},{},{},{},{},{},{}",
+                                csv_field( &quote.quote_time ), csv_field( &quote.venue ),
+                                csv_field( &quote.symbol ), quote.bid, quote.ask, quote.bid_size,
+                                quote.ask_size, quote.bid_depth, quote.ask_depth, quote.last,
+                                quote.last_size ) );
+            },
+            Format::LineProtocol => {
+                let nanos = match self.clock.wall().duration_since( UNIX_EPOCH ) {
+                    Ok( since ) => since.as_secs() * 1_000_000_000 + since.subsec_nanos() as u64,
+                    Err( _ ) => 0,
+                };
+                try!( writeln!( self.out,
+                                "quote,venue={},symbol={} bid={}i,ask={}i,bid_size={}i,ask_size={}i,bid_depth={}i,ask_depth={}i,last={}i,last_size={}i {}",
+                                tag_value( &quote.venue ), tag_value( &quote.symbol ), quote.bid,
+                                quote.ask, quote.bid_size, quote.ask_size, quote.bid_depth,
+                                quote.ask_depth, quote.last, quote.last_size, nanos ) );
+            },
+        }
+        try!( self.out.flush() );
+        Ok( () )
+    }
+
+    /// Hands back the underlying writer.
+    pub fn into_inner( self ) -> W {
+        self.out
+    }
+}
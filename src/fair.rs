@@ -0,0 +1,100 @@
+// Fair value estimates from the book.
+//
+// The plain mid ignores size: a book of 5 shares bid at 50.00 and 5000 offered at 50.10 is
+// far more likely to trade down to 50.00 than up to 50.10, and on thin Stockfighter books
+// that imbalance is the rule rather than the exception. The microprice weights each side's
+// price by the size on the *other* side, leaning the estimate towards the weaker side. The
+// depth-weighted variant does the same over the top few levels, which steadies it on books
+// where the touch is a single odd lot.
+//
+// Prices come back as f64 cents -- a fair value between two ticks is the whole point.
+
+use {Bid, OrderBook};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FairValue {
+    /// (bid + ask) / 2
+    Mid,
+    /// Touch prices weighted by the opposite side's size at the touch
+    Micro,
+    /// Like Micro, using the size-weighted average price and total size of the top N levels
+    /// of each side
+    Depth( usize ),
+}
+
+// Total size per price, best first, for the top `count` price levels
+fn top_levels( side: &[Bid], descending: bool, count: usize ) -> Vec<(i32, u64)> {
+    let mut levels: Vec<(i32, u64)> = vec![];
+    let mut prices: Vec<(i32, u64)> = side.iter().map( |level| ( level.price, level.qty.get() as u64 ) ).collect();
+    if descending {
+        prices.sort_by( |a, b| b.0.cmp( &a.0 ) );
+    } else {
+        prices.sort_by( |a, b| a.0.cmp( &b.0 ) );
+    }
+    for ( price, qty ) in prices {
+        let same_price = levels.last().map_or( false, |&( last, _ )| last == price );
+        if same_price {
+            let last = levels.len() - 1;
+            levels[last].1 += qty;
+        } else if levels.len() < count {
+            levels.push( ( price, qty ) );
+        } else {
+            break;
+        }
+    }
+    levels
+}
+
+// (size-weighted average price, total size) over `levels`
+fn weighted( levels: &[(i32, u64)] ) -> Option<(f64, f64)> {
+    let size: u64 = levels.iter().map( |&( _, qty )| qty ).sum();
+    if size == 0 {
+        return None;
+    }
+    let value: f64 = levels.iter().map( |&( price, qty )| price as f64 * qty as f64 ).sum();
+    Some( ( value / size as f64, size as f64 ) )
+}
+
+impl OrderBook {
+    /// The size-weighted mid, None unless both sides have orders.
+    ///
+    /// # Example
+    /// ```
+    /// // 1 share bid at 52.00, 151 offered at 52.05: fair value sits right on the bid
+    /// let book: market::OrderBook = market::decode::decode( market::fixtures::ORDER_BOOK ).unwrap();
+    /// let micro = book.microprice().unwrap();
+    /// assert!( micro > 5200.0 && micro < 5200.1 );
+    /// ```
+    pub fn microprice( &self ) -> Option<f64> {
+        self.fair_value( FairValue::Micro )
+    }
+
+    /// A fair value estimate by `formula`, None unless both sides have orders.
+    ///
+    /// # Example
+    /// ```
+    /// use market::fair::FairValue;
+    /// let mut book = market::OrderBook::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
+    /// book.refresh().unwrap();
+    /// println!("mid {:?} micro {:?} top-5 {:?}", book.fair_value( FairValue::Mid ),
+    ///          book.fair_value( FairValue::Micro ), book.fair_value( FairValue::Depth( 5 ) ) );
+    /// ```
+    pub fn fair_value( &self, formula: FairValue ) -> Option<f64> {
+        let count = match formula {
+            FairValue::Mid | FairValue::Micro => 1,
+            FairValue::Depth( levels ) => levels.max( 1 ),
+        };
+        let bid = weighted( &top_levels( &self.bids, true, count ) );
+        let ask = weighted( &top_levels( &self.asks, false, count ) );
+        let ( ( bid, bid_size ), ( ask, ask_size ) ) = match ( bid, ask ) {
+            ( Some( bid ), Some( ask ) ) => ( bid, ask ),
+            _ => return None,
+        };
+        match formula {
+            FairValue::Mid => Some( ( bid + ask ) / 2.0 ),
+            FairValue::Micro | FairValue::Depth( _ ) => {
+                Some( ( bid * ask_size + ask * bid_size ) / ( bid_size + ask_size ) )
+            },
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// Quote filters.
+//
+// Strategies rarely care about every quote -- usually only ones for a few symbols, or only when
+// the touch actually moved. A QuoteFilter is built up from a handful of simple conditions and
+// asked about each quote before it's handed on, so that noise never reaches the strategy.
+// Every condition that's been set has to pass for a quote to be accepted.
+
+use std::collections::{HashMap, HashSet};
+
+use {Qty, Quote};
+
+#[derive(Debug, Clone, Default)]
+pub struct QuoteFilter {
+    symbols: Option<HashSet<String>>,
+    min_last_size: Option<Qty>,
+    max_spread: Option<i32>,
+    on_touch_change: bool,
+    // (bid, ask) of the last accepted quote per symbol, for on_touch_change
+    last_touch: HashMap<String, (i32, i32)>,
+}
+
+impl QuoteFilter {
+    /// A filter that accepts everything until conditions are added.
+    ///
+    /// # Example
+    /// ```no_run
+    /// let mut filter = market::filter::QuoteFilter::new()
+    ///                      .symbols( &[ "FOOBAR" ] )
+    ///                      .max_spread( 25 )
+    ///                      .on_touch_change();
+    /// let client = market::StockfighterClient::new();
+    /// loop {
+    ///   let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    ///   if filter.accept( &quote ) {
+    ///     println!("touch moved: {} / {}", quote.bid, quote.ask );
+    ///   }
+    /// }
+    /// ```
+    pub fn new() -> QuoteFilter {
+        QuoteFilter::default()
+    }
+
+    /// Only quotes for these symbols.
+    pub fn symbols( mut self, symbols: &[&str] ) -> QuoteFilter {
+        self.symbols = Some( symbols.iter().map( |symbol| symbol.to_string() ).collect() );
+        self
+    }
+
+    /// Only quotes whose last trade was at least `size` shares.
+    pub fn min_last_size( mut self, size: Qty ) -> QuoteFilter {
+        self.min_last_size = Some( size );
+        self
+    }
+
+    /// Only two-sided quotes with a spread of at most `cents`.
+    pub fn max_spread( mut self, cents: i32 ) -> QuoteFilter {
+        self.max_spread = Some( cents );
+        self
+    }
+
+    /// Only quotes where the bid or ask price changed since the last accepted quote for the
+    /// same symbol.
+    pub fn on_touch_change( mut self ) -> QuoteFilter {
+        self.on_touch_change = true;
+        self
+    }
+
+    /// Whether `quote` passes every condition. Stateful: with `on_touch_change` set, accepting
+    /// a quote makes it the one later quotes are compared against.
+    pub fn accept( &mut self, quote: &Quote ) -> bool {
+        if let Some( ref symbols ) = self.symbols {
+            if !symbols.contains( &quote.symbol ) {
+                return false;
+            }
+        }
+        if let Some( size ) = self.min_last_size {
+            if quote.last_size < size {
+                return false;
+            }
+        }
+        if let Some( spread ) = self.max_spread {
+            // A missing side reads as 0, so a one-sided quote has no spread to speak of
+            if quote.bid <= 0 || quote.ask <= 0 || quote.ask - quote.bid > spread {
+                return false;
+            }
+        }
+        if self.on_touch_change {
+            let touch = ( quote.bid, quote.ask );
+            if self.last_touch.get( &quote.symbol ) == Some( &touch ) {
+                return false;
+            }
+            self.last_touch.insert( quote.symbol.clone(), touch );
+        }
+        true
+    }
+}
@@ -0,0 +1,74 @@
+// Captured API responses.
+//
+// Every body here is what the server really sends (venue and account names aside), kept under
+// fixtures/v1/ in the repository. The doc test below parses each one into the struct it
+// belongs to and checks the struct still knows every field in it, so a change to a field's
+// type or name that no longer matches the wire format fails the build's tests instead of a
+// bot's first request. When the API changes shape, add a fixtures/v2/ rather than editing
+// these -- servers speaking v1 are still out there.
+//
+// They're public so bots can use them too, e.g. to drive their own tests without a server.
+
+pub static HEARTBEAT: &'static str = include_str!( "../fixtures/v1/heartbeat.json" );
+pub static VENUE_HEARTBEAT: &'static str = include_str!( "../fixtures/v1/venue_heartbeat.json" );
+pub static VENUE_HEARTBEAT_DOWN: &'static str = include_str!( "../fixtures/v1/venue_heartbeat_down.json" );
+pub static VENUE_STOCKS: &'static str = include_str!( "../fixtures/v1/venue_stocks.json" );
+pub static ORDER_BOOK: &'static str = include_str!( "../fixtures/v1/order_book.json" );
+pub static ORDER_BOOK_EMPTY: &'static str = include_str!( "../fixtures/v1/order_book_empty.json" );
+pub static QUOTE: &'static str = include_str!( "../fixtures/v1/quote.json" );
+pub static QUOTE_ONE_SIDED: &'static str = include_str!( "../fixtures/v1/quote_one_sided.json" );
+pub static ORDER: &'static str = include_str!( "../fixtures/v1/order.json" );
+pub static ORDER_CANCELLED: &'static str = include_str!( "../fixtures/v1/order_cancelled.json" );
+pub static ACCOUNT_ORDERS: &'static str = include_str!( "../fixtures/v1/account_orders.json" );
+pub static ERROR: &'static str = include_str!( "../fixtures/v1/error.json" );
+
+/// Every fixture, by name.
+///
+/// # Example
+/// ```
+/// use market::decode::{checked, decode};
+/// use market::fixtures::*;
+/// use market::schema::unknown_fields;
+/// use market::visible::AccountOrders;
+/// use market::{OrderBook, OrderResponse, Quote, StockfighterAPI, StockfighterVenue,
+///              StockfighterVenueStocks};
+///
+/// decode::<StockfighterAPI>( HEARTBEAT ).unwrap();
+/// decode::<StockfighterVenue>( VENUE_HEARTBEAT ).unwrap();
+/// decode::<StockfighterVenue>( VENUE_HEARTBEAT_DOWN ).unwrap();
+/// assert_eq!( decode::<StockfighterVenueStocks>( VENUE_STOCKS ).unwrap().symbols.len(), 2 );
+/// decode::<OrderBook>( ORDER_BOOK ).unwrap();
+/// decode::<OrderBook>( ORDER_BOOK_EMPTY ).unwrap();
+/// assert_eq!( decode::<Quote>( QUOTE ).unwrap().ask, 5125 );
+/// assert_eq!( decode::<Quote>( QUOTE_ONE_SIDED ).unwrap().bid, 0 );
+/// let order: OrderResponse = checked( ORDER ).unwrap();
+/// assert_eq!( order.total_filled, market::Qty( 80 ) );
+/// assert_eq!( order.fills.len(), 2 );
+/// assert!( !checked::<OrderResponse>( ORDER_CANCELLED ).unwrap().open );
+/// assert_eq!( checked::<AccountOrders>( ACCOUNT_ORDERS ).unwrap().orders.len(), 1 );
+/// assert!( checked::<OrderResponse>( ERROR ).is_err() );
+///
+/// // Nothing the server sends goes unrecognised
+/// assert!( unknown_fields::<StockfighterAPI>( HEARTBEAT ).is_empty() );
+/// assert!( unknown_fields::<StockfighterVenue>( VENUE_HEARTBEAT ).is_empty() );
+/// assert!( unknown_fields::<StockfighterVenueStocks>( VENUE_STOCKS ).is_empty() );
+/// assert!( unknown_fields::<OrderBook>( ORDER_BOOK ).is_empty() );
+/// assert!( unknown_fields::<Quote>( QUOTE ).is_empty() );
+/// assert!( unknown_fields::<OrderResponse>( ORDER ).is_empty() );
+///
+/// assert_eq!( ALL.len(), 12 );
+/// ```
+pub static ALL: &'static [(&'static str, &'static str)] = &[
+    ( "heartbeat", HEARTBEAT ),
+    ( "venue_heartbeat", VENUE_HEARTBEAT ),
+    ( "venue_heartbeat_down", VENUE_HEARTBEAT_DOWN ),
+    ( "venue_stocks", VENUE_STOCKS ),
+    ( "order_book", ORDER_BOOK ),
+    ( "order_book_empty", ORDER_BOOK_EMPTY ),
+    ( "quote", QUOTE ),
+    ( "quote_one_sided", QUOTE_ONE_SIDED ),
+    ( "order", ORDER ),
+    ( "order_cancelled", ORDER_CANCELLED ),
+    ( "account_orders", ACCOUNT_ORDERS ),
+    ( "error", ERROR ),
+];
@@ -0,0 +1,174 @@
+// One backoff for the whole bot.
+//
+// When the server goes down, every part of a bot that talks to it -- the heartbeat watchdog,
+// the quote poller, the order queue -- notices on its own and retries on its own schedule.
+// Together that's a stampede: a burst of reconnects the moment anything comes back, from a
+// bot that's still half-broken because each piece is at a different point in its backoff.
+//
+// A HealthGate is shared (in an Arc) between all of them. Connection failures reported by any
+// component back the gate off for every component. Once the backoff has run out, exactly one
+// caller is let through to probe; if the probe succeeds, everyone resumes together, and if it
+// fails the backoff grows and the rest keep waiting.
+//
+// Only transport failures (connection refused, timeouts, broken pipes) count. A server that
+// answers -- even with an error -- is up.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use StockfighterErr;
+use cancel::CancelToken;
+use clock::{Clock, SystemClock};
+
+// How often a waiting caller rechecks the gate
+static WAIT_STEP_MS: u64 = 50;
+
+#[derive(Debug)]
+struct GateState {
+    failures: u32,
+    // While backed off, when the next probe may go. Granting a probe pushes it out by `max`,
+    // so a probe that never reports back holds the others up for a while, not forever
+    retry_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct HealthGate {
+    initial: Duration,
+    max: Duration,
+    state: Mutex<GateState>,
+    clock: Arc<Clock>,
+}
+
+/// True for errors that mean the server couldn't be reached at all.
+pub fn is_transport( err: &StockfighterErr ) -> bool {
+    match *err {
+        StockfighterErr::Hyper( _ ) | StockfighterErr::IO( _ ) => true,
+        _ => false,
+    }
+}
+
+impl HealthGate {
+    /// Backs off from `initial`, doubling on every failed probe up to `max`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use market::gate::HealthGate;
+    /// let gate = Arc::new( HealthGate::new( Duration::from_millis( 250 ), Duration::from_secs( 10 ) ) );
+    /// for _ in 0..4 {
+    ///   let gate = gate.clone();
+    ///   std::thread::spawn( move || {
+    ///     let client = market::StockfighterClient::new();
+    ///     loop {
+    ///       gate.wait();
+    ///       let quote = client.quote( "TESTEX", "FOOBAR" );
+    ///       gate.observe( &quote );
+    ///     }
+    ///   });
+    /// }
+    /// ```
+    pub fn new( initial: Duration, max: Duration ) -> HealthGate {
+        HealthGate {
+            initial: initial,
+            max: max,
+            state: Mutex::new( GateState { failures: 0, retry_at: None } ),
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    /// Reads the time and sleeps through `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> HealthGate {
+        self.clock = clock;
+        self
+    }
+
+    fn lock( &self ) -> ::std::sync::MutexGuard<GateState> {
+        self.state.lock().unwrap_or_else( |poisoned| poisoned.into_inner() )
+    }
+
+    pub fn is_healthy( &self ) -> bool {
+        self.lock().failures == 0
+    }
+
+    /// Whether the caller may send a request now. While the gate is backed off this is true
+    /// for a single caller once the backoff has run out -- that caller is the probe, and must
+    /// report how it went with `success` / `failure` (or `observe`).
+    pub fn ready( &self ) -> bool {
+        let now = self.clock.now();
+        let mut state = self.lock();
+        if state.failures == 0 {
+            return true;
+        }
+        match state.retry_at {
+            Some( at ) if at > now => false,
+            _ => {
+                state.retry_at = Some( now + self.max );
+                true
+            },
+        }
+    }
+
+    /// Blocks until `ready`.
+    pub fn wait( &self ) {
+        while !self.ready() {
+            self.clock.sleep( Duration::from_millis( WAIT_STEP_MS ) );
+        }
+    }
+
+    /// Blocks until `ready`, or returns Err(Cancelled) if `token` is cancelled first.
+    pub fn wait_or_cancel( &self, token: &CancelToken ) -> Result<(), StockfighterErr> {
+        self.wait_until( None, Some( token ) )
+    }
+
+    /// Blocks until `ready`, giving up with Err(TimedOut) at `until` or Err(Cancelled) once
+    /// `token` is cancelled, whichever applies first.
+    pub fn wait_until( &self, until: Option<Instant>, token: Option<&CancelToken> ) -> Result<(), StockfighterErr> {
+        let step = Duration::from_millis( WAIT_STEP_MS );
+        while !self.ready() {
+            if let Some( token ) = token {
+                try!( token.check() );
+            }
+            let now = self.clock.now();
+            let nap = match until {
+                Some( until ) if now >= until => return Err( StockfighterErr::TimedOut ),
+                Some( until ) if until - now < step => until - now,
+                _ => step,
+            };
+            self.clock.sleep( nap );
+        }
+        Ok( () )
+    }
+
+    /// The server answered: the gate opens for everyone.
+    pub fn success( &self ) {
+        let mut state = self.lock();
+        state.failures = 0;
+        state.retry_at = None;
+    }
+
+    /// The server couldn't be reached: everyone backs off.
+    pub fn failure( &self ) {
+        let now = self.clock.now();
+        let mut state = self.lock();
+        state.failures = state.failures.saturating_add( 1 );
+        let mut delay = self.initial;
+        for _ in 1..state.failures {
+            if delay >= self.max {
+                break;
+            }
+            delay = delay * 2;
+        }
+        let delay = if delay > self.max { self.max } else { delay };
+        state.retry_at = Some( now + delay );
+    }
+
+    /// Reports a request's outcome: transport errors are failures, anything else (including
+    /// an error the server sent back) is a success.
+    pub fn observe<T>( &self, result: &Result<T, StockfighterErr> ) {
+        match *result {
+            Err( ref err ) if is_transport( err ) => self.failure(),
+            _ => self.success(),
+        }
+    }
+}
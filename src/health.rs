@@ -0,0 +1,105 @@
+// Checking everything at once.
+//
+// A multi-venue bot's watchdog wants to know, every few seconds, whether the API is up and
+// whether any venue has wedged. Heartbeating them one after the other makes that take as long
+// as all the round trips added together; `heartbeat_all` sends them in parallel instead.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+
+use {StockfighterAPI, StockfighterVenue};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Health {
+    Up,
+    /// The server answered, but with ok: false (for a venue, that usually means wedged)
+    Down( String ),
+    /// The request itself failed
+    Unreachable( String ),
+}
+
+impl Health {
+    pub fn is_up( &self ) -> bool {
+        *self == Health::Up
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub api: Health,
+    pub venues: HashMap<String, Health>,
+}
+
+impl HealthReport {
+    /// True when the API and every venue are up.
+    pub fn all_up( &self ) -> bool {
+        self.api.is_up() && self.venues.values().all( |health| health.is_up() )
+    }
+}
+
+enum Target {
+    Api,
+    Venue( String ),
+}
+
+/// Heartbeats the API and each of `venues` concurrently, one thread per check, and waits for
+/// all of them.
+///
+/// # Example
+/// ```
+/// let venues = vec![ "TESTEX".to_owned(), "OBEX".to_owned() ];
+/// let report = market::health::heartbeat_all( &venues );
+/// if !report.all_up() {
+///   println!("{:#?}", report );
+/// }
+/// ```
+pub fn heartbeat_all( venues: &[String] ) -> HealthReport {
+    let ( tx, rx ) = mpsc::channel();
+    let mut targets = vec![ Target::Api ];
+    targets.extend( venues.iter().map( |venue| Target::Venue( venue.clone() ) ) );
+    let count = targets.len();
+
+    for target in targets {
+        let tx = tx.clone();
+        thread::spawn( move || {
+            let ( ok, error, failure ) = match target {
+                Target::Api => {
+                    let mut api = StockfighterAPI::new();
+                    let result = api.heartbeat();
+                    ( api.ok, api.error.clone(), result.err().map( |err| err.to_string() ) )
+                },
+                Target::Venue( ref venue ) => {
+                    let mut check = StockfighterVenue::new( venue.clone() );
+                    let result = check.heartbeat();
+                    ( check.ok, check.error.clone(), result.err().map( |err| err.to_string() ) )
+                },
+            };
+            let health = match failure {
+                Some( failure ) => Health::Unreachable( failure ),
+                None if ok => Health::Up,
+                None => Health::Down( error ),
+            };
+            // The receiver only goes away once every result is in
+            let _ = tx.send( ( target, health ) );
+        });
+    }
+    drop( tx );
+
+    let mut report = HealthReport {
+        api: Health::Unreachable( "no answer".to_owned() ),
+        venues: HashMap::new(),
+    };
+    for ( target, health ) in rx.iter().take( count ) {
+        match target {
+            Target::Api => report.api = health,
+            Target::Venue( venue ) => { report.venues.insert( venue, health ); },
+        }
+    }
+    // A check whose thread died never reported back
+    for venue in venues {
+        report.venues.entry( venue.clone() )
+                     .or_insert_with( || Health::Unreachable( "no answer".to_owned() ) );
+    }
+    report
+}
@@ -0,0 +1,129 @@
+// Intents describe *what* a strategy wants ("be long 500 FOOBAR", "quote both sides 10 cents
+// wide") and leave *how* to an ExecutionPolicy, which turns an intent plus the latest quote
+// into concrete orders. Swapping policies changes how aggressively a goal is worked without
+// touching the strategy that stated it.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use {Order, Qty, Quote};
+use clock::{Clock, SystemClock};
+
+#[derive(Debug, Clone)]
+pub enum Intent {
+    /// Get to a net position of `target` shares, as soon as possible
+    TargetPosition { venue: String, stock: String, target: i32 },
+    /// Get to a net position of `target` shares before `deadline`
+    TargetPositionBy { venue: String, stock: String, target: i32, deadline: SystemTime },
+    /// Rest `qty` shares on each side, `width` cents apart, centred on the mid
+    CaptureSpread { venue: String, stock: String, qty: Qty, width: i32 },
+}
+
+impl Intent {
+    /// "Buy `qty` shares by `deadline`", expressed as a target relative to the current position.
+    /// Pass a negative `qty` to sell.
+    pub fn buy_by( venue: String, stock: String, position: i32, qty: i32, deadline: SystemTime ) -> Intent {
+        Intent::TargetPositionBy {
+            venue: venue,
+            stock: stock,
+            target: position + qty,
+            deadline: deadline,
+        }
+    }
+}
+
+pub trait ExecutionPolicy {
+    /// Translates `intent` into the orders that should be sent now, given the latest `quote`
+    /// and the current net `position`. Returning no orders means there's nothing to do yet.
+    fn orders( &mut self, intent: &Intent, account: &str, quote: &Quote, position: i32 ) -> Vec<Order>;
+}
+
+fn limit( account: &str, venue: &str, stock: &str, price: i32, buy: bool, qty: Qty ) -> Order {
+    let direction = if buy { "buy" } else { "sell" };
+    Order::new( account.to_owned(),
+                venue.to_owned(),
+                stock.to_owned(),
+                price,
+                qty,
+                direction.to_owned(),
+                "limit".to_owned() )
+}
+
+/// A simple policy that works intents with limit orders at the touch, never sending more than
+/// `slice` shares in one order.
+///
+/// Urgent intents (`TargetPosition`, or a `TargetPositionBy` whose deadline has passed) cross
+/// the spread; a `TargetPositionBy` with time left joins the near side instead and waits to be
+/// filled.
+///
+/// # Example
+/// ```
+/// use market::intent::{Intent, ExecutionPolicy, TouchPolicy};
+/// let mut quote = market::Quote::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
+/// quote.get_quote().unwrap();
+/// let intent = Intent::TargetPosition { venue: "TESTEX".to_owned(),
+///                                       stock: "FOOBAR".to_owned(),
+///                                       target: 100 };
+/// let mut policy = TouchPolicy::new( market::Qty( 50 ) );
+/// for order in policy.orders( &intent, "EXB123456", &quote, 0 ) {
+///   order.process_order().unwrap();
+/// }
+/// ```
+pub struct TouchPolicy {
+    pub slice: Qty,
+    clock: Arc<Clock>,
+}
+
+impl TouchPolicy {
+    /// Sends at most `slice` shares per order.
+    pub fn new( slice: Qty ) -> TouchPolicy {
+        TouchPolicy { slice: slice, clock: Arc::new( SystemClock ) }
+    }
+
+    /// Judges `TargetPositionBy` deadlines by `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> TouchPolicy {
+        self.clock = clock;
+        self
+    }
+
+    fn work( &self, venue: &str, stock: &str, account: &str, quote: &Quote,
+             wanted: i32, aggressive: bool ) -> Vec<Order> {
+        if wanted == 0 {
+            return vec![];
+        }
+        let buy = wanted > 0;
+        let qty = Qty::magnitude( wanted ).min( self.slice );
+        // Buying aggressively means paying the ask; passively means joining the bid
+        let price = match ( buy, aggressive ) {
+            ( true, true ) | ( false, false ) => quote.ask,
+            ( true, false ) | ( false, true ) => quote.bid,
+        };
+        // A zero price means that side of the book is empty -- nothing sensible to send
+        if price <= 0 {
+            return vec![];
+        }
+        vec![ limit( account, venue, stock, price, buy, qty ) ]
+    }
+}
+
+impl ExecutionPolicy for TouchPolicy {
+    fn orders( &mut self, intent: &Intent, account: &str, quote: &Quote, position: i32 ) -> Vec<Order> {
+        match *intent {
+            Intent::TargetPosition { ref venue, ref stock, target } => {
+                self.work( venue, stock, account, quote, target - position, true )
+            },
+            Intent::TargetPositionBy { ref venue, ref stock, target, deadline } => {
+                let overdue = self.clock.wall() >= deadline;
+                self.work( venue, stock, account, quote, target - position, overdue )
+            },
+            Intent::CaptureSpread { ref venue, ref stock, qty, width } => {
+                if quote.bid <= 0 || quote.ask <= 0 {
+                    return vec![];
+                }
+                let mid = ( quote.bid + quote.ask ) / 2;
+                vec![ limit( account, venue, stock, mid - width / 2, true, qty ),
+                      limit( account, venue, stock, mid + width / 2, false, qty ) ]
+            },
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Decision journal.
+//
+// After a run it's easy to see *what* a bot did from its orders and fills, and much harder to
+// see *why*. Strategies call `journal.note( symbol, "skewing down, inventory 800" )` as they
+// make decisions; each note is timestamped and written as one JSON line to the journal's sink.
+// Point the sink at the same file you log quotes and fills to and the notes end up interleaved
+// with the market data in time order.
+
+use serde_json;
+
+use std::io::Write;
+use std::sync::Arc;
+
+use StockfighterErr;
+use clock::{Clock, SystemClock};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+    pub symbol: String,
+    pub note: String,
+}
+
+pub struct Journal {
+    sink: Option<Box<Write + Send>>,
+    entries: Vec<JournalEntry>,
+    clock: Arc<Clock>,
+}
+
+impl Journal {
+    /// A journal that only keeps entries in memory.
+    pub fn new() -> Journal {
+        Journal { sink: None, entries: vec![], clock: Arc::new( SystemClock ) }
+    }
+
+    /// A journal that also writes every entry to `sink` as a JSON line.
+    ///
+    /// # Example
+    /// ```
+    /// use std::fs::OpenOptions;
+    /// let log = OpenOptions::new().create( true ).append( true ).open( "run.jsonl" ).unwrap();
+    /// let mut journal = market::journal::Journal::to_writer( log );
+    /// journal.note( "FOOBAR", "skewing down, inventory 800" ).unwrap();
+    /// ```
+    pub fn to_writer<W: Write + Send + 'static>( sink: W ) -> Journal {
+        Journal { sink: Some( Box::new( sink ) ), entries: vec![], clock: Arc::new( SystemClock ) }
+    }
+
+    /// Stamps entries using `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> Journal {
+        self.clock = clock;
+        self
+    }
+
+    /// Records a note about `symbol`, stamped with the current time.
+    pub fn note( &mut self, symbol: &str, note: &str ) -> Result<(), StockfighterErr> {
+        let entry = JournalEntry {
+            ts: self.clock.millis(),
+            symbol: symbol.to_owned(),
+            note: note.to_owned(),
+        };
+        if let Some( ref mut sink ) = self.sink {
+            let line = try!( serde_json::to_string( &entry ) );
+            try!( writeln!( sink, "{}", line ) );
+        }
+        self.entries.push( entry );
+        Ok( () )
+    }
+
+    pub fn entries( &self ) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Entries about `symbol`, oldest first.
+    pub fn for_symbol( &self, symbol: &str ) -> Vec<&JournalEntry> {
+        self.entries.iter().filter( |entry| entry.symbol == symbol ).collect()
+    }
+}
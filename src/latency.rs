@@ -0,0 +1,140 @@
+// Fill latency statistics.
+//
+// How long does a limit order at the touch take to get its first fill on this venue? And to
+// fill completely? The answer decides whether it's worth crossing the spread. FillLatency
+// times every order it's shown from submission, and keeps a rolling window of samples per
+// (symbol, order type) to take percentiles over.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use {Order, OrderResponse, StockfighterErr};
+use clock::{Clock, SystemClock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Milestone {
+    /// Submission to the first fill
+    FirstFill,
+    /// Submission to the order being completely filled
+    Complete,
+}
+
+#[derive(Debug, Clone)]
+struct InFlight {
+    key: (String, String),
+    submitted: Instant,
+    first_fill: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FillLatency {
+    window: usize,
+    in_flight: HashMap<i32, InFlight>,
+    samples: HashMap<(String, String, Milestone), VecDeque<Duration>>,
+    clock: Arc<Clock>,
+}
+
+impl FillLatency {
+    /// Keeps the most recent `window` samples per (symbol, order type, milestone).
+    pub fn new( window: usize ) -> FillLatency {
+        FillLatency {
+            window: window.max( 1 ),
+            in_flight: HashMap::new(),
+            samples: HashMap::new(),
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    /// Times submissions with `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> FillLatency {
+        self.clock = clock;
+        self
+    }
+
+    /// Sends `order`, timing it from just before the request goes out. Any fills that came
+    /// back with the response itself are counted straight away.
+    ///
+    /// # Example
+    /// ```
+    /// use market::latency::{FillLatency, Milestone};
+    /// let mut latency = FillLatency::new( 100 );
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// latency.submit( &order ).unwrap();
+    /// if let Some( p50 ) = latency.percentile( "FOOBAR", "limit", Milestone::FirstFill, 50.0 ) {
+    ///   println!("median time to first fill: {:?}", p50 );
+    /// }
+    /// ```
+    pub fn submit( &mut self, order: &Order ) -> Result<OrderResponse, StockfighterErr> {
+        let submitted = self.clock.now();
+        let response = try!( order.process_order() );
+        self.submitted( &response, submitted );
+        let now = self.clock.now();
+        self.observe( &response, now );
+        Ok( response )
+    }
+
+    /// Starts timing an order that was sent some other way, from `at`.
+    pub fn submitted( &mut self, response: &OrderResponse, at: Instant ) {
+        self.in_flight.insert( response.id, InFlight {
+            key: ( response.symbol.clone(), response.order_type.clone() ),
+            submitted: at,
+            first_fill: false,
+        });
+    }
+
+    /// Feeds in a newer status for a timed order (eg. from polling it). Orders that aren't
+    /// being timed are ignored. Once an order is closed it stops being tracked.
+    pub fn observe( &mut self, response: &OrderResponse, at: Instant ) {
+        let mut done = false;
+        let mut recorded = vec![];
+        if let Some( flight ) = self.in_flight.get_mut( &response.id ) {
+            // A replay can hand in an `at` from before the submit; that counts as no time at all
+            let elapsed = if at >= flight.submitted { at - flight.submitted } else { Duration::from_secs( 0 ) };
+            if !flight.first_fill && !response.fills.is_empty() {
+                flight.first_fill = true;
+                recorded.push( ( flight.key.clone(), Milestone::FirstFill, elapsed ) );
+            }
+            let complete = !response.original_qty.is_zero() && response.total_filled >= response.original_qty;
+            if complete {
+                recorded.push( ( flight.key.clone(), Milestone::Complete, elapsed ) );
+            }
+            done = complete || !response.open;
+        }
+        for ( ( symbol, order_type ), milestone, elapsed ) in recorded {
+            let window = self.window;
+            let samples = self.samples.entry( ( symbol, order_type, milestone ) )
+                                      .or_insert_with( VecDeque::new );
+            samples.push_back( elapsed );
+            while samples.len() > window {
+                samples.pop_front();
+            }
+        }
+        if done {
+            self.in_flight.remove( &response.id );
+        }
+    }
+
+    /// The `p`th percentile (0 - 100) of the recorded latencies, nearest-rank, or None if
+    /// nothing has been recorded for that combination yet.
+    pub fn percentile( &self, symbol: &str, order_type: &str, milestone: Milestone, p: f64 )
+                       -> Option<Duration> {
+        let samples = match self.samples.get( &( symbol.to_owned(), order_type.to_owned(), milestone ) ) {
+            Some( samples ) if !samples.is_empty() => samples,
+            _ => return None,
+        };
+        let mut sorted: Vec<Duration> = samples.iter().cloned().collect();
+        sorted.sort();
+        let p = p.max( 0.0 ).min( 100.0 );
+        let rank = ( ( p / 100.0 ) * sorted.len() as f64 ).ceil() as usize;
+        Some( sorted[ rank.max( 1 ) - 1 ] )
+    }
+
+    /// How many samples are in the window for that combination.
+    pub fn count( &self, symbol: &str, order_type: &str, milestone: Milestone ) -> usize {
+        self.samples.get( &( symbol.to_owned(), order_type.to_owned(), milestone ) )
+                    .map_or( 0, |samples| samples.len() )
+    }
+}
@@ -0,0 +1,91 @@
+// The Stockfighter levels, by name.
+//
+// The GameMaster knows levels by their URL name ("chock_a_block"), which is easy to mistype
+// and says nothing about what the level wants. Level names the known ones, with a little
+// metadata for helpers and reports; `Custom` carries anything else through unchanged, for new
+// levels and community servers.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Level {
+    FirstSteps,
+    ChockABlock,
+    SellSide,
+    DuelingBulldozers,
+    IrrationalExuberance,
+    MakingAmends,
+    Custom( String ),
+}
+
+pub static KNOWN: &'static [Level] = &[
+    Level::FirstSteps,
+    Level::ChockABlock,
+    Level::SellSide,
+    Level::DuelingBulldozers,
+    Level::IrrationalExuberance,
+    Level::MakingAmends,
+];
+
+impl Level {
+    /// The level for a GameMaster name, or `Custom` if it isn't one we know.
+    ///
+    /// # Example
+    /// ```
+    /// use market::level::Level;
+    /// assert_eq!( Level::from_name( "chock_a_block" ), Level::ChockABlock );
+    /// assert_eq!( Level::from_name( "chock_a_block" ).instruments(), Some( 1 ) );
+    /// assert_eq!( Level::from_name( "my_level" ).name(), "my_level" );
+    /// ```
+    pub fn from_name( name: &str ) -> Level {
+        match name {
+            "first_steps" => Level::FirstSteps,
+            "chock_a_block" => Level::ChockABlock,
+            "sell_side" => Level::SellSide,
+            "dueling_bulldozers" => Level::DuelingBulldozers,
+            "irrational_exuberance" => Level::IrrationalExuberance,
+            "making_amends" => Level::MakingAmends,
+            other => Level::Custom( other.to_owned() ),
+        }
+    }
+
+    /// The name the GameMaster uses in its URLs.
+    pub fn name( &self ) -> &str {
+        match *self {
+            Level::FirstSteps => "first_steps",
+            Level::ChockABlock => "chock_a_block",
+            Level::SellSide => "sell_side",
+            Level::DuelingBulldozers => "dueling_bulldozers",
+            Level::IrrationalExuberance => "irrational_exuberance",
+            Level::MakingAmends => "making_amends",
+            Level::Custom( ref name ) => name,
+        }
+    }
+
+    /// How many symbols the level trades, if known.
+    pub fn instruments( &self ) -> Option<usize> {
+        match *self {
+            Level::Custom( _ ) => None,
+            _ => Some( 1 ),
+        }
+    }
+
+    /// What it takes to pass, in a sentence, if known.
+    pub fn goal( &self ) -> Option<&'static str> {
+        match *self {
+            Level::FirstSteps => Some( "Buy 100 shares of the stock" ),
+            Level::ChockABlock => Some( "Buy 100,000 shares below the target price without moving the market" ),
+            Level::SellSide => Some( "Make $10,000 market making while keeping the position within 1,000 shares" ),
+            Level::DuelingBulldozers => Some( "Make $250,000 market making against a competing bot" ),
+            Level::IrrationalExuberance => Some( "Run the price up and profit from the bubble" ),
+            Level::MakingAmends => Some( "Find the account trading on inside information" ),
+            Level::Custom( _ ) => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
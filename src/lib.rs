@@ -4,16 +4,148 @@
 extern crate hyper;
 extern crate serde;
 extern crate serde_json;
+#[cfg(feature = "gzip")]
+extern crate flate2;
 
 
 use hyper::Client;
 use hyper::header::Connection;
 use hyper::header::Headers;
+use hyper::method::Method;
 
 use std::mem;
 use std::io::Read;
 use std::error::Error;
 use std::fmt;
+use std::time::Instant;
+
+// With the `tracing` feature enabled these open spans / emit events (see market::trace).
+// Without it they compile away to nothing, arguments included.
+#[cfg(feature = "tracing")]
+macro_rules! trace_span {
+    ( $name:expr $(, $key:ident = $value:expr )* ) => {
+        let _span = ::trace::Span::enter( $name, vec![ $( ( stringify!( $key ), $value.to_string() ) ),* ] );
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_span {
+    ( $($arg:tt)* ) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ( $name:expr $(, $key:ident = $value:expr )* ) => {
+        ::trace::event( $name, vec![ $( ( stringify!( $key ), $value.to_string() ) ),* ] );
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_event {
+    ( $($arg:tt)* ) => {};
+}
+
+// The REST client itself -- always built
+pub mod auth;
+pub mod budget;
+pub mod cancel;
+pub mod client;
+pub mod clock;
+pub mod datadir;
+pub mod deadline;
+pub mod decode;
+pub mod fixtures;
+pub mod gate;
+pub mod health;
+pub mod level;
+pub mod meta;
+pub mod poll;
+pub mod prelude;
+pub mod pretrade;
+pub mod price;
+pub mod qty;
+pub mod raw;
+mod render;
+pub mod route;
+pub mod schema;
+pub mod sizing;
+pub mod visible;
+
+// Market data analysis (feature "analytics", on by default)
+#[cfg(feature = "analytics")]
+pub mod anomaly;
+#[cfg(feature = "analytics")]
+pub mod consolidated;
+#[cfg(feature = "analytics")]
+pub mod depth;
+#[cfg(feature = "analytics")]
+pub mod event;
+#[cfg(feature = "analytics")]
+pub mod export;
+#[cfg(feature = "analytics")]
+pub mod fair;
+#[cfg(feature = "analytics")]
+pub mod filter;
+#[cfg(feature = "analytics")]
+pub mod latency;
+#[cfg(feature = "analytics")]
+pub mod recording;
+#[cfg(feature = "analytics")]
+pub mod sampler;
+#[cfg(feature = "analytics")]
+pub mod scanner;
+#[cfg(feature = "analytics")]
+pub mod series;
+#[cfg(feature = "analytics")]
+pub mod signals;
+#[cfg(feature = "analytics")]
+pub mod symbols;
+#[cfg(feature = "analytics")]
+pub mod tape;
+
+// Strategy building blocks (feature "strategy", on by default)
+#[cfg(feature = "strategy")]
+pub mod alerts;
+#[cfg(feature = "strategy")]
+pub mod blotter;
+#[cfg(feature = "strategy")]
+pub mod chaos;
+#[cfg(feature = "strategy")]
+pub mod closeout;
+#[cfg(feature = "strategy")]
+pub mod drawdown;
+#[cfg(feature = "strategy")]
+pub mod experiments;
+#[cfg(feature = "strategy")]
+pub mod intent;
+#[cfg(feature = "strategy")]
+pub mod journal;
+#[cfg(feature = "strategy")]
+pub mod mark;
+#[cfg(feature = "strategy")]
+pub mod notify;
+#[cfg(feature = "strategy")]
+pub mod queue;
+#[cfg(feature = "strategy")]
+pub mod quotepair;
+#[cfg(feature = "strategy")]
+pub mod risk;
+#[cfg(feature = "strategy")]
+pub mod router;
+#[cfg(feature = "strategy")]
+pub mod scheduler;
+#[cfg(feature = "strategy")]
+pub mod supervisor;
+
+// Instrumentation (feature "tracing", off by default)
+#[cfg(feature = "tracing")]
+pub mod trace;
+
+pub use auth::Auth;
+pub use client::StockfighterClient;
+pub use meta::Meta;
+pub use qty::Qty;
+pub use route::Route;
 
 static STOCKFIGHTER_API_URL: &'static str = "https://api.stockfighter.io/ob/api";
 
@@ -23,6 +155,19 @@ pub enum StockfighterErr {
     Serde(serde_json::error::Error),
     IO(std::io::Error),
     NoSuchVenue(String),
+    BadRoute(String),
+    BadPrice(String),
+    NoApiKey,
+    EmptyBody,
+    NotJson(String),
+    Api(String),
+    DeadlineExceeded,
+    WorkerDied,
+    TimedOut,
+    Cancelled,
+    BadRule(String),
+    Rejected(String),
+    VenueWedged(String),
 
 }
 
@@ -51,6 +196,19 @@ impl fmt::Display for StockfighterErr {
             StockfighterErr::Serde( ref err ) => err.fmt(f),
             StockfighterErr::IO( ref err ) => err.fmt(f),
             StockfighterErr::NoSuchVenue( ref err ) => write!(f, "{}", err),
+            StockfighterErr::BadRoute( ref err ) => write!(f, "Bad route: {}", err),
+            StockfighterErr::BadPrice( ref err ) => write!(f, "Can't parse price: {}", err),
+            StockfighterErr::NoApiKey => write!(f, "STOCKFIGHTERAPI is not set"),
+            StockfighterErr::EmptyBody => write!(f, "Server sent an empty body"),
+            StockfighterErr::NotJson( ref body ) => write!(f, "Server sent something other than JSON: {}", body),
+            StockfighterErr::Api( ref err ) => write!(f, "API error: {}", err),
+            StockfighterErr::DeadlineExceeded => write!(f, "Order not acknowledged within its latency budget"),
+            StockfighterErr::WorkerDied => write!(f, "Background worker exited without a result"),
+            StockfighterErr::TimedOut => write!(f, "Gave up waiting: deadline passed"),
+            StockfighterErr::Cancelled => write!(f, "Cancelled"),
+            StockfighterErr::BadRule( ref rule ) => write!(f, "Bad alert rule: {}", rule),
+            StockfighterErr::Rejected( ref why ) => write!(f, "Order rejected before sending: {}", why),
+            StockfighterErr::VenueWedged( ref venue ) => write!(f, "Venue {} is wedged", venue),
         }
     }
 }
@@ -62,6 +220,19 @@ impl Error for StockfighterErr {
             StockfighterErr::Serde( ref err ) => err.description(),
             StockfighterErr::IO( ref err ) => err.description(),
             StockfighterErr::NoSuchVenue( _ ) => "Venue Doesn't Exist",
+            StockfighterErr::BadRoute( _ ) => "Malformed URL component",
+            StockfighterErr::BadPrice( _ ) => "Unparseable or sub-cent price",
+            StockfighterErr::NoApiKey => "No API key available",
+            StockfighterErr::EmptyBody => "Empty response body",
+            StockfighterErr::NotJson( _ ) => "Response body is not JSON",
+            StockfighterErr::Api( _ ) => "Server answered ok: false",
+            StockfighterErr::DeadlineExceeded => "Latency budget exceeded",
+            StockfighterErr::WorkerDied => "Background worker died",
+            StockfighterErr::TimedOut => "Timed out",
+            StockfighterErr::Cancelled => "Cancelled",
+            StockfighterErr::BadRule( _ ) => "Bad alert rule",
+            StockfighterErr::Rejected( _ ) => "Order rejected before sending",
+            StockfighterErr::VenueWedged( _ ) => "Venue is wedged",
         }
     }
 }
@@ -116,16 +287,15 @@ impl StockfighterVenue {
     /// ```
     pub fn heartbeat(&mut self) -> Result<bool, StockfighterErr> {
         self.ok = false;
-        let url = format!("{}/venues/{}/heartbeat",
-                          STOCKFIGHTER_API_URL.to_owned(),
-                          self.venue);
+        let url = try!( Route::VenueHeartbeat { venue: &self.venue }.url() );
+        trace_span!( "http", op = "venue_heartbeat", url = url );
         let mut body = String::new();
         let client = Client::new();
         let mut response = try!(client.get(&url)
                                 .header(Connection::close())
                                 .send() );
         try!( response.read_to_string( &mut body ) );
-        let deserialized = try!(serde_json::from_str( &body ));
+        let deserialized = try!( decode::decode( &body ) );
         mem::replace( self, deserialized );
         Ok( self.ok )
     }
@@ -137,9 +307,23 @@ impl StockfighterVenue {
             error: "".to_owned(),
         }
     }
+
+    /// Whether this venue lists `symbol`. Uses the client's cached stock listing, so checking
+    /// a whole strategy configuration at startup costs one request per venue.
+    ///
+    /// # Example
+    /// ```
+    /// let client = market::StockfighterClient::new();
+    /// let venue = market::StockfighterVenue::new( "TESTEX".to_owned() );
+    /// assert!( venue.symbol_exists( &client, "FOOBAR" ).unwrap() );
+    /// ```
+    pub fn symbol_exists(&self, client: &StockfighterClient, symbol: &str) -> Result<bool, StockfighterErr> {
+        let stocks = try!( client.stocks( &self.venue ) );
+        Ok( stocks.iter().any( |stock| stock.symbol == symbol ) )
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Stock {
     pub name: String,
     pub symbol: String,
@@ -160,16 +344,15 @@ impl StockfighterVenueStocks {
     }
 
     pub fn stock_listing( &mut self, venue: String) -> Result<bool, StockfighterErr> {
-        let url = format!("{}/venues/{}/stocks",
-                          STOCKFIGHTER_API_URL.to_owned(),
-                          venue);
+        let url = try!( Route::VenueStocks { venue: &venue }.url() );
+        trace_span!( "http", op = "stock_listing", url = url );
         let mut body = String::new();
         let client = Client::new();
         let mut response = try!(client.get(&url)
                                   .header(Connection::close())
                                   .send() );
         try!( response.read_to_string( &mut body ) );
-        let deserialized: StockfighterVenueStocks = try!(serde_json::from_str(&body) ); 
+        let deserialized: StockfighterVenueStocks = try!( decode::decode( &body ) ); 
         mem::replace( self, deserialized );
         Ok( self.ok )
     }
@@ -201,14 +384,15 @@ impl StockfighterAPI {
     /// ```
     pub fn heartbeat(&mut self) -> Result<bool, StockfighterErr> {
         self.ok = false;
-        let url = format!("{}/heartbeat", STOCKFIGHTER_API_URL.to_owned());
+        let url = try!( Route::Heartbeat.url() );
+        trace_span!( "http", op = "api_heartbeat", url = url );
         let mut body = String::new();
         let client = Client::new();
         let mut response = try!(client.get(&url)
                                  .header(Connection::close())
                                  .send() );
         try!( response.read_to_string( &mut body ) );
-        let deserialized: StockfighterAPI = try!(serde_json::from_str(&body) );
+        let deserialized: StockfighterAPI = try!( decode::decode( &body ) );
         mem::replace( self, deserialized );
         Ok(self.ok)
     }
@@ -221,12 +405,41 @@ impl StockfighterAPI {
     }
 }
 
-pub fn get_apikey() -> String {
-    env!("STOCKFIGHTERAPI").to_string()
+/// The API key, from the `STOCKFIGHTERAPI` environment variable.
+///
+/// The variable is read at runtime; if it isn't set, a key baked in at build time (by having
+/// `STOCKFIGHTERAPI` set when the crate was compiled) is used instead. With neither, you get
+/// `StockfighterErr::NoApiKey` rather than a panic.
+///
+/// # Example
+/// ```
+/// use market::StockfighterErr;
+/// std::env::remove_var( "STOCKFIGHTERAPI" );
+/// match market::get_apikey() {
+///     Err( StockfighterErr::NoApiKey ) => {},
+///     // Only when a key was baked in at build time
+///     Ok( _ ) => assert!( option_env!("STOCKFIGHTERAPI").is_some() ),
+///     Err( err ) => panic!( "unexpected error: {}", err ),
+/// }
+///
+/// // An empty variable counts as unset
+/// std::env::set_var( "STOCKFIGHTERAPI", "" );
+/// assert!( market::get_apikey().is_err() || option_env!("STOCKFIGHTERAPI").is_some() );
+/// ```
+pub fn get_apikey() -> Result<String, StockfighterErr> {
+    match std::env::var( "STOCKFIGHTERAPI" ) {
+        Ok( ref key ) if !key.is_empty() => Ok( key.clone() ),
+        _ => match option_env!("STOCKFIGHTERAPI") {
+            Some( key ) if !key.is_empty() => Ok( key.to_owned() ),
+            _ => Err( StockfighterErr::NoApiKey ),
+        },
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OrderResponse {
+    // Absent on the entries of an account's order list
+    #[serde(default)]
     pub ok: bool,
     #[serde(default)]
     pub error: String,
@@ -237,9 +450,9 @@ pub struct OrderResponse {
     #[serde(default)]
     pub direction: String,
     #[serde(default, rename="originalQty")]
-    pub original_qty: i32,
+    pub original_qty: Qty,
     #[serde(default)]
-    pub qty: i32,
+    pub qty: Qty,
     #[serde(default)]
     pub price: i32,
     #[serde(default, rename="orderType")]
@@ -253,29 +466,29 @@ pub struct OrderResponse {
     #[serde(default)]
     pub fills: Vec<OrderFill>,
     #[serde(default, rename="totalFilled")]
-    pub total_filled: i32,
+    pub total_filled: Qty,
     #[serde(default)]
     pub open: bool,
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OrderFill {
     #[serde(default)]
     pub price: i32,
     #[serde(default)]
-    pub qty: i32,
+    pub qty: Qty,
     #[serde(default)]
     pub ts: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Order {
     pub account: String,
     pub venue: String,
     pub stock: String,
     pub price: i32,
-    pub qty: i32,
+    pub qty: Qty,
     pub direction: String,
     #[serde(rename="orderType")]
     pub order_type: String,
@@ -286,7 +499,7 @@ impl Order {
                venue: String,
                stock: String,
                price: i32,
-               qty: i32,
+               qty: Qty,
                direction: String,
                order_type: String)
                -> Order {
@@ -301,42 +514,168 @@ impl Order {
         }
     }
 
-    fn encode_order(&self) -> Result< String, StockfighterErr > {
+    /// The JSON body process_order sends for this order.
+    pub fn encode_order(&self) -> Result< String, StockfighterErr > {
         let return_string = try!(serde_json::to_string(&self) );
         Ok( return_string.to_string() )
     }
 
-    fn order_url(&self) -> String {
-        let return_string = format!("{}/venues/{}/stocks/{}/orders",
-                                    STOCKFIGHTER_API_URL.to_owned(),
-                                    self.venue,
-                                    self.stock);
-        return_string
+    fn order_url(&self) -> Result< String, StockfighterErr > {
+        Route::Orders { venue: &self.venue, stock: &self.stock }.url()
     }
 
+    /// Sends the order. An order the server refuses (`ok: false`) comes back as
+    /// `Err( StockfighterErr::Api( reason ) )`.
     pub fn process_order(&self) -> Result< OrderResponse, StockfighterErr > {
-        let header_vec: Vec<Vec<u8>> = vec!( get_apikey().as_bytes().to_vec() );
+        self.process_order_with( &Auth::Env )
+    }
+
+    /// Same as process_order, but authenticating with `auth` -- for servers that don't take
+    /// the key the way the official one does.
+    pub fn process_order_with(&self, auth: &Auth) -> Result< OrderResponse, StockfighterErr > {
+        self.process_order_with_meta( auth ).map( |( response, _ )| response )
+    }
+
+    /// Same as process_order_with, also returning the HTTP status, headers and round trip
+    /// time of the submission.
+    pub fn process_order_with_meta(&self, auth: &Auth) -> Result< (OrderResponse, Meta), StockfighterErr > {
         let body: String = try!( self.encode_order() );
-        let url = self.order_url(); 
+        self.send_encoded( &body, auth )
+    }
+
+    /// Sends `body` (normally from encode_order) as this order's submission, byte for byte.
+    /// For callers that need to keep a record of exactly what went out.
+    pub fn send_encoded(&self, body: &str, auth: &Auth) -> Result< (OrderResponse, Meta), StockfighterErr > {
+        let url = try!( self.order_url() );
+        trace_span!( "order", venue = self.venue, stock = self.stock, direction = self.direction,
+                     qty = self.qty, price = self.price );
         let mut headers = Headers::new();
-        headers.set_raw("X-Starfighter-Authorization", header_vec);
+        try!( auth.apply( &mut headers ) );
         let client = Client::new();
-        let mut response = try!( client.post( &url )
-                                .body( &body )
-                                .headers( headers )
-                                .send() );
+        trace_event!( "submit", url = url );
+        let started = Instant::now();
+        let mut response = {
+            trace_span!( "http", op = "order", url = url );
+            try!( client.post( &url )
+                        .body( body )
+                        .headers( headers )
+                        .send() )
+        };
+        let meta = Meta::from_response( &response, started );
         let mut body = String::new();
         try!( response.read_to_string( &mut body ));
-        let deserialized = try!(serde_json::from_str( &body ));
+        let deserialized: OrderResponse = try!( decode::checked( &body ) );
+        trace_event!( "ack", id = deserialized.id, ok = deserialized.ok );
+        trace_fills( &deserialized );
+        Ok( ( deserialized, meta ) )
+
+    }
+
+}
+
+impl OrderResponse {
+    /// Fetches the order's current state from the venue.
+    pub fn status(&self) -> Result< OrderResponse, StockfighterErr > {
+        self.status_with( &Auth::Env )
+    }
+
+    /// Same as status, authenticating with `auth`.
+    pub fn status_with(&self, auth: &Auth) -> Result< OrderResponse, StockfighterErr > {
+        self.order_call( Method::Get, auth )
+    }
+
+    /// Cancels whatever is left of the order, returning its final state -- including any
+    /// fills that landed before the cancel did.
+    ///
+    /// # Example
+    /// ```
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// let resting = order.process_order().unwrap();
+    /// let last = resting.cancel().unwrap();
+    /// println!("{} filled before the cancel", last.total_filled );
+    /// ```
+    pub fn cancel(&self) -> Result< OrderResponse, StockfighterErr > {
+        self.cancel_with( &Auth::Env )
+    }
+
+    /// Same as cancel, authenticating with `auth`.
+    pub fn cancel_with(&self, auth: &Auth) -> Result< OrderResponse, StockfighterErr > {
+        self.order_call( Method::Delete, auth )
+    }
+
+    /// The order as it was originally placed, ready to send again -- say, after a cancel.
+    ///
+    /// # Example
+    /// ```
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// let resting = order.process_order().unwrap();
+    /// let cancelled = resting.cancel().unwrap();
+    /// if let Some( rest ) = cancelled.remainder() {
+    ///   let requoted = market::Order { price: 5010, ..rest };
+    ///   requoted.process_order().unwrap();
+    /// }
+    /// ```
+    pub fn to_order(&self) -> Order {
+        Order::new( self.account.clone(),
+                    self.venue.clone(),
+                    self.symbol.clone(),
+                    self.price,
+                    self.original_qty,
+                    self.direction.clone(),
+                    self.order_type.clone() )
+    }
+
+    /// The unfilled part of the order as a fresh Order, or None if it filled completely.
+    pub fn remainder(&self) -> Option<Order> {
+        let left = self.original_qty.saturating_sub( self.total_filled );
+        if left == Qty( 0 ) {
+            return None;
+        }
+        let mut order = self.to_order();
+        order.qty = left;
+        Some( order )
+    }
+
+    fn order_call(&self, method: Method, auth: &Auth) -> Result< OrderResponse, StockfighterErr > {
+        let url = try!( Route::Order { venue: &self.venue, stock: &self.symbol, id: self.id }.url() );
+        trace_span!( "http", op = method, url = url );
+        let mut headers = Headers::new();
+        try!( auth.apply( &mut headers ) );
+        let client = Client::new();
+        let mut response = try!( client.request( method, &url ).headers( headers ).send() );
+        let mut body = String::new();
+        try!( response.read_to_string( &mut body ) );
+        let deserialized: OrderResponse = try!( decode::checked( &body ) );
+        trace_fills( &deserialized );
         Ok( deserialized )
+    }
+}
 
+// One "fill" event per fill, tagged with the order id so a trace can be grouped by order
+#[cfg(feature = "tracing")]
+fn trace_fills( response: &OrderResponse ) {
+    for fill in &response.fills {
+        trace_event!( "fill", id = response.id, price = fill.price, qty = fill.qty, ts = fill.ts );
     }
+}
 
+#[cfg(not(feature = "tracing"))]
+fn trace_fills( _: &OrderResponse ) {}
+
+impl<'a> From<&'a OrderResponse> for Order {
+    fn from( response: &'a OrderResponse ) -> Order {
+        response.to_order()
+    }
 }
 
 // This would normally be an enum. However, given that we may want to try and break things later
 // making it a struct will make it easier to programmatically pass something other than the four
 // actual order types, but will also make it harder to accidentally make a typo.
+// For actually breaking things, see raw::RawRequest.
 pub struct OrderType {
     #[serde(rename="Limit")]
     limit: String,
@@ -350,38 +689,44 @@ pub struct OrderType {
 
 impl OrderType {}
 
-#[derive( Serialize, Deserialize, Debug )]
+#[derive( Serialize, Deserialize, Debug, Clone )]
 pub struct Bid {
     price: i32,
-    qty: i32,
+    qty: Qty,
     #[serde(rename="isBuy")]
     is_buy: bool,
 }
 
-#[derive( Serialize, Deserialize, Debug )]
+#[derive( Serialize, Deserialize, Debug, Clone )]
 pub struct OrderBook {
     ok: bool,
     venue: String,
     symbol: String,
+    // An empty side comes over the wire as null rather than []
+    #[serde(default, deserialize_with="null_as_empty")]
     bids: Vec<Bid>,
+    #[serde(default, deserialize_with="null_as_empty")]
     asks: Vec<Bid>,
     ts: String,
 }
 
+fn null_as_empty<D: serde::Deserializer>( deserializer: &mut D ) -> Result<Vec<Bid>, D::Error> {
+    let side: Option<Vec<Bid>> = try!( serde::Deserialize::deserialize( deserializer ) );
+    Ok( side.unwrap_or( vec![] ) )
+}
+
 impl OrderBook {
     pub fn refresh(&mut self) -> Result<bool, StockfighterErr> {
         self.ok = false;
-        let url = format!("{}/venues/{}/stocks/{}",
-                          STOCKFIGHTER_API_URL.to_owned(),
-                          self.venue,
-                          self.symbol);
+        let url = try!( Route::OrderBook { venue: &self.venue, stock: &self.symbol }.url() );
+        trace_span!( "http", op = "order_book", url = url );
         let mut body = String::new();
         let client = Client::new();
         let mut response = try!(client.get(&url)
                                   .header(Connection::close())
                                   .send() );
         try!( response.read_to_string( &mut body ) );
-        let deserialized: OrderBook = try!(serde_json::from_str(&body) );
+        let deserialized: OrderBook = try!( decode::checked( &body ) );
         mem::replace( self, deserialized );
         Ok(self.ok)
     }
@@ -398,7 +743,7 @@ impl OrderBook {
     }
 }
 
-#[derive( Debug, Serialize, Deserialize )]
+#[derive( Debug, Clone, Serialize, Deserialize )]
 pub struct Quote {
     pub ok: bool,
     pub symbol: String,
@@ -408,17 +753,17 @@ pub struct Quote {
     #[serde(default)]
     pub ask: i32,
     #[serde(default, rename="bidSize")]
-    pub bid_size: i32,
+    pub bid_size: Qty,
     #[serde(default, rename="askSize")]
-    pub ask_size: i32,
+    pub ask_size: Qty,
     #[serde(default, rename="bidDepth")]
-    pub bid_depth: i32,
+    pub bid_depth: Qty,
     #[serde(default, rename="askDepth")]
-    pub ask_depth: i32,
+    pub ask_depth: Qty,
     #[serde(default)]
     pub last: i32,
     #[serde(default, rename="lastSize")]
-    pub last_size: i32,
+    pub last_size: Qty,
     #[serde(default, rename="lastTrade")]
     pub last_trade: String,
     #[serde(default, rename="quoteTime")]
@@ -435,12 +780,12 @@ impl Quote {
             venue: venue,
             bid: 0,
             ask: 0,
-            bid_size: 0,
-            ask_size: 0,
-            bid_depth: 0,
-            ask_depth: 0,
+            bid_size: Qty( 0 ),
+            ask_size: Qty( 0 ),
+            bid_depth: Qty( 0 ),
+            ask_depth: Qty( 0 ),
             last: 0,
-            last_size: 0,
+            last_size: Qty( 0 ),
             last_trade: "".to_owned(),
             quote_time: "".to_owned(),
         }
@@ -468,30 +813,33 @@ impl Quote {
     /// let mut quote = market::Quote::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
     /// let response = quote.get_quote();
     /// match response {
+    ///   Err( market::StockfighterErr::Api( message ) ) => {
+    ///     println!("The venue said no (does the venue/stock exist?): {}", message );
+    ///   },
     ///   Err( e ) => {
     ///     println!("We hit a snag: {:?}", e );
     ///   },
     ///   Ok( val ) => {
-    ///      println!("Query worked. But does the venue/stock exist? This bool knows: {}", val );
+    ///      println!("Query worked: {}", val );
     ///   }
     /// }
     /// println!("Now we can do things with the actual quote struct: {:#?}", quote );
     /// ```
     pub fn get_quote( & mut self ) -> Result< bool, StockfighterErr > {
         self.ok = false;
-        let url = format!("{}/venues/{}/stocks/{}/quote",
-                          STOCKFIGHTER_API_URL.to_owned(),
-                          self.venue,
-                          self.symbol);
+        let url = try!( Route::Quote { venue: &self.venue, stock: &self.symbol }.url() );
+        trace_span!( "http", op = "quote", url = url );
         let mut body = String::new();
         let client = Client::new();
         let mut response = try!(client.get(&url)
                                   .header(Connection::close())
                                   .send() );
         try!( response.read_to_string( &mut body ) );
-        let deserialized: Quote = try!(serde_json::from_str(&body) );
+        // An ok: false reply is an Api error and leaves the struct as it was, rather than
+        // replacing it with defaults
+        let deserialized: Quote = try!( decode::checked( &body ) );
         mem::replace( self,  deserialized );
-        Ok( true )
+        Ok( self.ok )
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+// Marking positions to market.
+//
+// Unrealized P&L depends on what price a position is marked at, and the last trade is a poor
+// choice on a wide venue: 1000 shares bought at the ask and marked at a last print on the
+// bid side look like an instant loss that isn't real -- or, worse, a gain that is. Marking
+// chooses the price per symbol: mid, last, or the touch a position would actually exit at
+// (the bid for a long, the ask for a short).
+
+use std::collections::HashMap;
+
+use Quote;
+use blotter::Blotter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkSource {
+    /// Halfway between bid and ask
+    Mid,
+    /// The last trade price
+    Last,
+    /// The price the position would exit at: bid when long, ask when short
+    Exit,
+}
+
+/// One symbol's line in a valuation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkedPosition {
+    pub symbol: String,
+    pub position: i32,
+    /// Net cash flow from fills in this symbol, in cents
+    pub cash: i64,
+    /// The mark price used, or None if the quote couldn't provide one (or there was no quote)
+    pub mark: Option<i32>,
+    /// cash + position * mark, in cents; None without a mark for a non-flat position
+    pub pnl: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marking {
+    default: MarkSource,
+    symbols: HashMap<String, MarkSource>,
+}
+
+impl Marking {
+    /// Marks every symbol using `default` unless overridden.
+    pub fn new( default: MarkSource ) -> Marking {
+        Marking { default: default, symbols: HashMap::new() }
+    }
+
+    /// Marks `symbol` using `source` instead of the default.
+    pub fn symbol( mut self, symbol: &str, source: MarkSource ) -> Marking {
+        self.symbols.insert( symbol.to_owned(), source );
+        self
+    }
+
+    pub fn source_for( &self, symbol: &str ) -> MarkSource {
+        self.symbols.get( symbol ).cloned().unwrap_or( self.default )
+    }
+
+    /// The price to mark `position` shares of `quote`'s symbol at. None when the side of the
+    /// book the source needs is empty.
+    pub fn price( &self, quote: &Quote, position: i32 ) -> Option<i32> {
+        let price = match self.source_for( &quote.symbol ) {
+            MarkSource::Mid => {
+                if quote.bid > 0 && quote.ask > 0 { ( quote.bid + quote.ask ) / 2 } else { 0 }
+            },
+            MarkSource::Last => quote.last,
+            MarkSource::Exit => if position >= 0 { quote.bid } else { quote.ask },
+        };
+        if price > 0 { Some( price ) } else { None }
+    }
+
+    /// Values every symbol `tag` has traded against the latest `quotes`.
+    ///
+    /// # Example
+    /// ```
+    /// use market::blotter::Blotter;
+    /// use market::mark::{Marking, MarkSource};
+    /// let client = market::StockfighterClient::new();
+    /// let blotter = Blotter::new();
+    /// let marking = Marking::new( MarkSource::Mid ).symbol( "FOOBAR", MarkSource::Exit );
+    /// let quotes = vec![ client.quote( "TESTEX", "FOOBAR" ).unwrap() ];
+    /// for line in marking.value( &blotter, "mm", &quotes ) {
+    ///   println!("{} {} @ {:?}: {:?}", line.symbol, line.position, line.mark, line.pnl );
+    /// }
+    /// ```
+    pub fn value( &self, blotter: &Blotter, tag: &str, quotes: &[Quote] ) -> Vec<MarkedPosition> {
+        blotter.symbols( tag ).into_iter().map( |symbol| {
+            let position = blotter.position_in( tag, &symbol );
+            let cash = blotter.cash_in( tag, &symbol );
+            let mark = quotes.iter()
+                             .find( |quote| quote.symbol == symbol )
+                             .and_then( |quote| self.price( quote, position ) );
+            let pnl = match mark {
+                Some( mark ) => Some( cash + position as i64 * mark as i64 ),
+                None if position == 0 => Some( cash ),
+                None => None,
+            };
+            MarkedPosition { symbol: symbol, position: position, cash: cash, mark: mark, pnl: pnl }
+        }).collect()
+    }
+
+    /// Total P&L across `value`'s lines, or None if any non-flat position couldn't be marked.
+    pub fn total( &self, blotter: &Blotter, tag: &str, quotes: &[Quote] ) -> Option<i64> {
+        self.value( blotter, tag, quotes ).iter().fold( Some( 0 ), |total, line| {
+            match ( total, line.pnl ) {
+                ( Some( total ), Some( pnl ) ) => Some( total + pnl ),
+                _ => None,
+            }
+        })
+    }
+}
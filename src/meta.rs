@@ -0,0 +1,37 @@
+// Response metadata for callers who want more than the parsed body.
+//
+// The normal calls throw away everything but the JSON. The `*_with_meta` variants also hand
+// back the HTTP status, the response headers (in case the server starts sending rate-limit
+// hints) and how long the round trip took, so a bot can adapt to what the server is telling it.
+
+use hyper::client::Response;
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Meta {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// From just before the request was sent until the response headers arrived
+    pub elapsed: Duration,
+}
+
+impl Meta {
+    pub fn from_response( response: &Response, started: Instant ) -> Meta {
+        Meta {
+            status: response.status.to_u16(),
+            headers: response.headers.iter()
+                             .map( |header| ( header.name().to_owned(), header.value_string() ) )
+                             .collect(),
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// The first header called `name`, ignoring case.
+    pub fn header( &self, name: &str ) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers.iter()
+                    .find( |&&( ref header, _ )| header.to_lowercase() == name )
+                    .map( |&( _, ref value )| value.as_str() )
+    }
+}
@@ -0,0 +1,192 @@
+// Getting a human's attention.
+//
+// A bot running half attended needs a way to say "come and look": a large fill, a tripped
+// circuit breaker, a level won or lost. Notifier sends the events you care about to one or
+// more sinks -- a webhook (Slack, Discord and most chat tools take a JSON POST), a desktop
+// notification, or just the terminal bell -- and ignores the rest.
+//
+// A sink that fails (the webhook is down, no notification daemon) doesn't stop the others;
+// `notify` returns the first error after trying them all.
+
+use hyper::Client;
+use hyper::header::{ContentType, Headers};
+use serde_json;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::process::Command;
+
+use {Qty, StockfighterErr};
+use price::format_price;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Fill { symbol: String, direction: String, qty: Qty, price: i32 },
+    /// A risk limit tripped (see drawdown::Drawdown)
+    CircuitBreaker( String ),
+    /// An alert rule fired (see alerts::Alerts)
+    Alert( String ),
+    /// The level ended, with whatever the GM said about it
+    LevelComplete( String ),
+}
+
+impl Event {
+    /// A short machine-friendly name: fill, circuit_breaker, alert or level_complete.
+    pub fn kind( &self ) -> &'static str {
+        match *self {
+            Event::Fill { .. } => "fill",
+            Event::CircuitBreaker( _ ) => "circuit_breaker",
+            Event::Alert( _ ) => "alert",
+            Event::LevelComplete( _ ) => "level_complete",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+        match *self {
+            Event::Fill { ref symbol, ref direction, qty, price } => {
+                write!(f, "{} {} {} @ {}", direction, qty, symbol, format_price( price ))
+            },
+            Event::CircuitBreaker( ref why ) => write!(f, "circuit breaker: {}", why),
+            Event::Alert( ref rule ) => write!(f, "alert: {}", rule),
+            Event::LevelComplete( ref outcome ) => write!(f, "level complete: {}", outcome),
+        }
+    }
+}
+
+pub trait Sink {
+    fn send( &mut self, event: &Event ) -> Result<(), StockfighterErr>;
+}
+
+/// POSTs `{"event": kind, "text": description}` as JSON to a URL.
+pub struct Webhook {
+    url: String,
+    client: Client,
+}
+
+impl Webhook {
+    pub fn new( url: String ) -> Webhook {
+        Webhook { url: url, client: Client::new() }
+    }
+}
+
+impl Sink for Webhook {
+    fn send( &mut self, event: &Event ) -> Result<(), StockfighterErr> {
+        let mut payload = BTreeMap::new();
+        payload.insert( "event", event.kind().to_owned() );
+        payload.insert( "text", event.to_string() );
+        let body = try!( serde_json::to_string( &payload ) );
+        let mut headers = Headers::new();
+        headers.set( ContentType::json() );
+        let response = try!( self.client.post( &self.url ).headers( headers ).body( &body ).send() );
+        let status = response.status.to_u16();
+        if status < 200 || status >= 300 {
+            return Err( StockfighterErr::Api( format!("webhook answered {}", status) ) );
+        }
+        Ok( () )
+    }
+}
+
+/// A desktop notification, through notify-send on Linux and osascript on macOS.
+pub struct Desktop;
+
+impl Sink for Desktop {
+    fn send( &mut self, event: &Event ) -> Result<(), StockfighterErr> {
+        let text = event.to_string();
+        let status = if cfg!( target_os = "macos" ) {
+            let script = format!("display notification \"{}\" with title \"market\"", text.replace( "\"", "'" ) );
+            try!( Command::new( "osascript" ).arg( "-e" ).arg( script ).status() )
+        } else {
+            try!( Command::new( "notify-send" ).arg( "market" ).arg( text ).status() )
+        };
+        if !status.success() {
+            return Err( StockfighterErr::IO( io::Error::new( io::ErrorKind::Other, "notifier exited with an error" ) ) );
+        }
+        Ok( () )
+    }
+}
+
+/// The terminal bell and a line of text, on stderr.
+pub struct Bell;
+
+impl Sink for Bell {
+    fn send( &mut self, event: &Event ) -> Result<(), StockfighterErr> {
+        try!( writeln!( io::stderr(), "\x07{}", event ) );
+        Ok( () )
+    }
+}
+
+pub struct Notifier {
+    sinks: Vec<Box<Sink>>,
+    kinds: Option<Vec<&'static str>>,
+    min_fill_value: i64,
+}
+
+impl Notifier {
+    /// A notifier with no sinks, passing every kind of event.
+    ///
+    /// # Example
+    /// ```
+    /// use market::notify::{Bell, Event, Notifier, Webhook};
+    /// let mut notifier = Notifier::new()
+    ///                        .sink( Bell )
+    ///                        .sink( Webhook::new( "https://hooks.example.com/T000/B000".to_owned() ) )
+    ///                        .min_fill_value( 1000000 );
+    /// notifier.notify( &Event::Fill { symbol: "FOOBAR".to_owned(), direction: "buy".to_owned(),
+    ///                                 qty: market::Qty( 5000 ), price: 4550 } ).ok();
+    /// ```
+    pub fn new() -> Notifier {
+        Notifier { sinks: vec![], kinds: None, min_fill_value: 0 }
+    }
+
+    pub fn sink<S: Sink + 'static>( mut self, sink: S ) -> Notifier {
+        self.sinks.push( Box::new( sink ) );
+        self
+    }
+
+    /// Only passes events whose `kind` is listed.
+    pub fn only( mut self, kinds: &[&'static str] ) -> Notifier {
+        self.kinds = Some( kinds.to_vec() );
+        self
+    }
+
+    /// Ignores fills worth less than `cents` (qty * price).
+    pub fn min_fill_value( mut self, cents: i64 ) -> Notifier {
+        self.min_fill_value = cents;
+        self
+    }
+
+    /// Whether `event` would be sent.
+    pub fn wants( &self, event: &Event ) -> bool {
+        if let Some( ref kinds ) = self.kinds {
+            if !kinds.contains( &event.kind() ) {
+                return false;
+            }
+        }
+        match *event {
+            Event::Fill { qty, price, .. } => qty.get() as i64 * price as i64 >= self.min_fill_value,
+            _ => true,
+        }
+    }
+
+    /// Sends `event` to every sink, if it's wanted.
+    pub fn notify( &mut self, event: &Event ) -> Result<(), StockfighterErr> {
+        if !self.wants( event ) {
+            return Ok( () );
+        }
+        let mut first_error = None;
+        for sink in &mut self.sinks {
+            if let Err( err ) = sink.send( event ) {
+                if first_error.is_none() {
+                    first_error = Some( err );
+                }
+            }
+        }
+        match first_error {
+            Some( err ) => Err( err ),
+            None => Ok( () ),
+        }
+    }
+}
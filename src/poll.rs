@@ -0,0 +1,219 @@
+// Polling loops.
+//
+// "Fetch something every 250ms until it looks right, or give up after 30s" -- poll an order
+// until it's filled, poll a quote until the spread tightens. Everyone ends up writing this loop
+// by hand, usually without a deadline and without a way to stop it from another thread.
+// `poll_until` is that loop; Poller is the same loop with the optional extras: a cancel token,
+// a callback whenever the watched value changes, a shared gate::HealthGate to back off
+// through when the server is down, a shared budget::RateBudget to read from, and an
+// injectable clock.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use StockfighterErr;
+use budget::{Class, RateBudget};
+use cancel::CancelToken;
+use clock::{Clock, SystemClock};
+use gate::{is_transport, HealthGate};
+
+/// Calls `fetch` every `interval` until `predicate` accepts what it returned, and hands that
+/// value back. Gives up with `StockfighterErr::TimedOut` once `deadline` has passed. An error
+/// from `fetch` ends the loop and is returned as is.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use market::poll::poll_until;
+/// let client = market::StockfighterClient::new();
+/// let quote = poll_until( Duration::from_millis( 250 ), Duration::from_secs( 30 ),
+///                         || client.quote( "TESTEX", "FOOBAR" ),
+///                         |quote| quote.ask > 0 && quote.ask - quote.bid < 10 );
+/// match quote {
+///   Ok( quote ) => println!("spread tightened: {} / {}", quote.bid, quote.ask ),
+///   Err( e ) => println!("gave up: {}", e ),
+/// }
+/// ```
+pub fn poll_until<T, F, P>( interval: Duration, deadline: Duration, fetch: F, predicate: P )
+                            -> Result<T, StockfighterErr>
+    where F: FnMut() -> Result<T, StockfighterErr>,
+          P: FnMut( &T ) -> bool
+{
+    Poller::new( interval ).deadline( deadline ).run( fetch, predicate )
+}
+
+pub struct Poller<'a, T> {
+    interval: Duration,
+    deadline: Option<Duration>,
+    cancel: Option<CancelToken>,
+    on_change: Option<Box<FnMut( &T ) + 'a>>,
+    gate: Option<Arc<HealthGate>>,
+    budget: Option<Arc<RateBudget>>,
+    clock: Arc<Clock>,
+}
+
+impl<'a, T> Poller<'a, T> {
+    /// Polls every `interval`, with no deadline until one is set.
+    pub fn new( interval: Duration ) -> Poller<'a, T> {
+        Poller {
+            interval: interval,
+            deadline: None,
+            cancel: None,
+            on_change: None,
+            gate: None,
+            budget: None,
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    /// Gives up with `TimedOut` once `deadline` has passed since `run` was called.
+    pub fn deadline( mut self, deadline: Duration ) -> Poller<'a, T> {
+        self.deadline = Some( deadline );
+        self
+    }
+
+    /// Stops with `Cancelled` as soon as `flag` is set, checked before every fetch.
+    pub fn cancel_on( mut self, flag: Arc<AtomicBool> ) -> Poller<'a, T> {
+        self.cancel = Some( CancelToken::from_flag( flag ) );
+        self
+    }
+
+    /// Stops with `Cancelled` as soon as `token` is cancelled, including part way through the
+    /// wait between fetches.
+    pub fn cancel_with( mut self, token: CancelToken ) -> Poller<'a, T> {
+        self.cancel = Some( token );
+        self
+    }
+
+    /// Calls `callback` with every fetched value whose `key` differs from the previous one's,
+    /// including the first. Watch a key rather than the whole value, so a field like a quote's
+    /// timestamp -- different on every fetch -- doesn't count as a change.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use market::poll::Poller;
+    /// let client = market::StockfighterClient::new();
+    /// Poller::new( Duration::from_millis( 250 ) )
+    ///   .deadline( Duration::from_secs( 60 ) )
+    ///   .on_change( |quote: &market::Quote| ( quote.bid, quote.ask ),
+    ///               |quote| println!("touch moved: {} / {}", quote.bid, quote.ask ) )
+    ///   .run( || client.quote( "TESTEX", "FOOBAR" ), |quote| quote.last > 10000 )
+    ///   .ok();
+    /// ```
+    pub fn on_change<K, F, G>( mut self, key: F, mut callback: G ) -> Poller<'a, T>
+        where K: PartialEq + 'a,
+              F: Fn( &T ) -> K + 'a,
+              G: FnMut( &T ) + 'a
+    {
+        let mut last: Option<K> = None;
+        self.on_change = Some( Box::new( move |value: &T| {
+            let current = key( value );
+            if last.as_ref() != Some( &current ) {
+                callback( value );
+                last = Some( current );
+            }
+        }));
+        self
+    }
+
+    /// Waits on `gate` before every fetch and reports each fetch's outcome to it, so polling
+    /// backs off together with everything else sharing the gate. A fetch that fails to reach
+    /// the server is retried (within the deadline) rather than ending the loop.
+    pub fn gate( mut self, gate: Arc<HealthGate> ) -> Poller<'a, T> {
+        self.gate = Some( gate );
+        self
+    }
+
+    /// Spends a Class::Read request from `budget` before every fetch, waiting for one if the
+    /// read budget is spent -- but no longer than the deadline allows, and not past a cancel.
+    /// A read budget of zero ends the loop with `Rejected`.
+    pub fn budget( mut self, budget: Arc<RateBudget> ) -> Poller<'a, T> {
+        self.budget = Some( budget );
+        self
+    }
+
+    /// Reads the time and sleeps through `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> Poller<'a, T> {
+        self.clock = clock;
+        self
+    }
+
+    /// Polls until `predicate` accepts a fetched value, returning it.
+    pub fn run<F, P>( mut self, mut fetch: F, mut predicate: P ) -> Result<T, StockfighterErr>
+        where F: FnMut() -> Result<T, StockfighterErr>,
+              P: FnMut( &T ) -> bool
+    {
+        let started = self.clock.now();
+        loop {
+            if let Some( ref token ) = self.cancel {
+                try!( token.check() );
+            }
+            if let Some( ref gate ) = self.gate {
+                // A backed-off gate mustn't hold the loop past its own deadline
+                let until = self.deadline.map( |deadline| started + deadline );
+                try!( gate.wait_until( until, self.cancel.as_ref() ) );
+            }
+            if let Some( ref budget ) = self.budget {
+                // Nor may a starved shared budget
+                while !budget.try_take( Class::Read ) {
+                    let wait = match budget.wait_time( Class::Read ) {
+                        Some( wait ) => wait.max( Duration::from_millis( 1 ) ),
+                        None => return Err( StockfighterErr::Rejected( "the read budget is zero".to_owned() ) ),
+                    };
+                    let wait = match self.deadline {
+                        Some( deadline ) => {
+                            let now = self.clock.now();
+                            if now >= started + deadline {
+                                return Err( StockfighterErr::TimedOut );
+                            }
+                            wait.min( started + deadline - now )
+                        },
+                        None => wait,
+                    };
+                    match self.cancel {
+                        Some( ref token ) => try!( token.sleep( &*self.clock, wait ) ),
+                        None => self.clock.sleep( wait ),
+                    }
+                }
+            }
+            let fetched = fetch();
+            let value = match ( fetched, self.gate.as_ref() ) {
+                ( Err( err ), Some( gate ) ) => {
+                    if !is_transport( &err ) {
+                        gate.success();
+                        return Err( err );
+                    }
+                    gate.failure();
+                    if let Some( deadline ) = self.deadline {
+                        if self.clock.now() > started + deadline {
+                            return Err( StockfighterErr::TimedOut );
+                        }
+                    }
+                    continue;
+                },
+                ( Ok( value ), Some( gate ) ) => {
+                    gate.success();
+                    value
+                },
+                ( fetched, None ) => try!( fetched ),
+            };
+            if let Some( ref mut on_change ) = self.on_change {
+                on_change( &value );
+            }
+            if predicate( &value ) {
+                return Ok( value );
+            }
+            if let Some( deadline ) = self.deadline {
+                if self.clock.now() + self.interval > started + deadline {
+                    return Err( StockfighterErr::TimedOut );
+                }
+            }
+            match self.cancel {
+                Some( ref token ) => try!( token.sleep( &*self.clock, self.interval ) ),
+                None => self.clock.sleep( self.interval ),
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+// The types almost every bot touches, in one import:
+//
+//     use market::prelude::*;
+//
+// Only things that are here to stay go in. A name dropped from the prelude breaks every
+// downstream glob import at once, so additions are cheap and removals aren't made lightly.
+
+pub use {Order, OrderBook, OrderFill, OrderResponse, Quote, StockfighterErr, StockfighterVenue};
+pub use auth::Auth;
+pub use cancel::CancelToken;
+pub use client::StockfighterClient;
+pub use meta::Meta;
+pub use qty::Qty;
+pub use sizing::Rounding;
+
+#[cfg(feature = "strategy")]
+pub use blotter::Blotter;
+#[cfg(feature = "strategy")]
+pub use intent::{ExecutionPolicy, Intent};
+#[cfg(feature = "strategy")]
+pub use quotepair::{QuotePair, Target};
+#[cfg(feature = "strategy")]
+pub use scheduler::Scheduler;
@@ -0,0 +1,240 @@
+// Client-side checks run on an order before it goes out.
+//
+// The classic copy-paste mistake is an order for yesterday's level: the right account, the
+// wrong venue or symbol. The server happily rejects some of these and happily *accepts* others
+// (a test venue trades anything). PreTrade knows which venues and symbols this session is
+// meant to be trading and refuses orders for anything else -- or, with `warn_only`, lets them
+// through with a line on stderr.
+//
+// Given the latest quote it can also catch priced orders far through the market: a sell at 45
+// when the bid is 45.40 means someone sent dollars where the API wants cents, and a buy at
+// 455000 against a 45.50 ask is the same slip the other way (cents converted twice).
+// Per-symbol price bands catch the same 100x mistake without a quote, and `audit` makes the
+// checks panic on any band violation -- for test runs, where a units bug should stop
+// everything.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use {Order, OrderResponse, Quote, StockfighterErr};
+use price::format_price;
+use auth::Auth;
+use client::StockfighterClient;
+
+#[derive(Debug, Clone, Default)]
+pub struct PreTrade {
+    venues: BTreeSet<String>,
+    symbols: BTreeSet<(String, String)>,
+    through: Option<f64>,
+    bands: BTreeMap<String, (i32, i32)>,
+    audit: bool,
+    warn_only: bool,
+}
+
+impl PreTrade {
+    /// A guard that knows no venues yet, and so passes any venue until one is added.
+    ///
+    /// # Example
+    /// ```
+    /// use market::pretrade::PreTrade;
+    /// let guard = PreTrade::new().symbol( "TESTEX", "FOOBAR" );
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TSTEX".to_owned(), "FOOBAR".to_owned(),
+    ///                                 4550, market::Qty( 100 ), "buy".to_owned(), "limit".to_owned() );
+    /// assert!( guard.check( &order ).is_err() );
+    /// ```
+    pub fn new() -> PreTrade {
+        PreTrade::default()
+    }
+
+    /// Allows any symbol on `venue`.
+    pub fn venue( mut self, venue: &str ) -> PreTrade {
+        self.venues.insert( venue.to_owned() );
+        self
+    }
+
+    /// Allows `symbol` on `venue`. Once a venue has symbols listed, only those pass.
+    pub fn symbol( mut self, venue: &str, symbol: &str ) -> PreTrade {
+        self.venues.insert( venue.to_owned() );
+        self.symbols.insert( ( venue.to_owned(), symbol.to_owned() ) );
+        self
+    }
+
+    /// Allows every symbol in `venue`'s stock listing.
+    pub fn load_listing( mut self, client: &StockfighterClient, venue: &str ) -> Result<PreTrade, StockfighterErr> {
+        for stock in try!( client.stocks( venue ) ) {
+            self = self.symbol( venue, &stock.symbol );
+        }
+        Ok( self )
+    }
+
+    /// Flags buys priced more than `tolerance` (a fraction: 0.05 is 5%) above the ask, and
+    /// sells that far below the bid, in `check_against` -- every order type with a price, so
+    /// limit, immediate-or-cancel and fill-or-kill alike. Without an ask (or bid) on
+    /// the quote the last trade price is used; with neither the order passes.
+    ///
+    /// # Example
+    /// ```
+    /// use market::pretrade::PreTrade;
+    /// let guard = PreTrade::new().limit_through( 0.05 );
+    /// let mut quote = market::Quote::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
+    /// quote.bid = 4540;
+    /// quote.ask = 4550;
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(), "FOOBAR".to_owned(),
+    ///                                 455000, market::Qty( 100 ), "buy".to_owned(), "limit".to_owned() );
+    /// assert!( guard.check_against( &order, &quote ).is_err() );
+    /// ```
+    pub fn limit_through( mut self, tolerance: f64 ) -> PreTrade {
+        self.through = Some( tolerance );
+        self
+    }
+
+    /// Only lets orders for `symbol` through priced between `low` and `high` cents, inclusive.
+    ///
+    /// # Example
+    /// ```
+    /// use market::pretrade::PreTrade;
+    /// let guard = PreTrade::new().price_band( "FOOBAR", 1000, 10000 );
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(), "FOOBAR".to_owned(),
+    ///                                 45, market::Qty( 100 ), "buy".to_owned(), "limit".to_owned() );
+    /// assert!( guard.check( &order ).is_err() );
+    /// ```
+    pub fn price_band( mut self, symbol: &str, low: i32, high: i32 ) -> PreTrade {
+        self.bands.insert( symbol.to_owned(), ( low, high ) );
+        self
+    }
+
+    /// A band from `reference / factor` to `reference * factor`: `band_around( "FOOBAR", 4550, 5 )`
+    /// allows 9.10 to 227.50, and still catches a 100x slip either way.
+    pub fn band_around( self, symbol: &str, reference: i32, factor: i32 ) -> PreTrade {
+        let factor = factor.max( 1 );
+        self.price_band( symbol, reference / factor, reference.saturating_mul( factor ) )
+    }
+
+    /// Makes `check` and `submit` (and their `_against` forms) panic on a price outside its
+    /// symbol's band, whatever `warn_only` says. `problem` still just reports it. Meant for
+    /// debug and test runs.
+    pub fn audit( mut self, audit: bool ) -> PreTrade {
+        self.audit = audit;
+        self
+    }
+
+    /// Reports problems on stderr instead of rejecting the order.
+    pub fn warn_only( mut self, warn_only: bool ) -> PreTrade {
+        self.warn_only = warn_only;
+        self
+    }
+
+    // The band check on its own, so `audit` can tell it apart from the rest
+    fn band_problem( &self, order: &Order ) -> Option<String> {
+        if order.order_type == "market" {
+            return None;
+        }
+        match self.bands.get( &order.stock ) {
+            Some( &( low, high ) ) if order.price < low || order.price > high => {
+                Some( format!("{} price {} cents (${}) is outside the plausible band ${} to ${}",
+                              order.stock, order.price, format_price( order.price ),
+                              format_price( low ), format_price( high )) )
+            },
+            _ => None,
+        }
+    }
+
+    /// What's wrong with `order`, if anything, regardless of `warn_only`.
+    pub fn problem( &self, order: &Order ) -> Option<String> {
+        if let Some( why ) = self.band_problem( order ) {
+            return Some( why );
+        }
+        if self.venues.is_empty() {
+            return None;
+        }
+        if !self.venues.contains( &order.venue ) {
+            return Some( format!("{} is not one of this session's venues ({})", order.venue,
+                                 self.venues.iter().cloned().collect::<Vec<_>>().join( ", " )) );
+        }
+        let listed = self.symbols.iter().any( |&( ref venue, _ )| *venue == order.venue );
+        if listed && !self.symbols.contains( &( order.venue.clone(), order.stock.clone() ) ) {
+            return Some( format!("{} is not traded on {} in this session", order.stock, order.venue) );
+        }
+        None
+    }
+
+    /// What's wrong with `order` given the latest `quote`, if anything.
+    pub fn problem_against( &self, order: &Order, quote: &Quote ) -> Option<String> {
+        if let Some( why ) = self.problem( order ) {
+            return Some( why );
+        }
+        let tolerance = match self.through {
+            // Everything but a market order carries a limit price, and immediate-or-cancel /
+            // fill-or-kill are the ones most likely to trade straight through
+            Some( tolerance ) if order.order_type != "market" => tolerance,
+            _ => return None,
+        };
+        let buy = order.direction == "buy";
+        let touch = match ( buy, quote.ask, quote.bid ) {
+            ( true, ask, _ ) if ask > 0 => ask,
+            ( false, _, bid ) if bid > 0 => bid,
+            _ => quote.last,
+        };
+        if touch <= 0 {
+            return None;
+        }
+        let price = order.price as f64;
+        let touch = touch as f64;
+        if buy && price > touch * ( 1.0 + tolerance ) {
+            return Some( format!("buy at {} is more than {:.1}% through the ask {} -- dollars instead of cents?",
+                                 order.price, tolerance * 100.0, touch) );
+        }
+        if !buy && price < touch * ( 1.0 - tolerance ) {
+            return Some( format!("sell at {} is more than {:.1}% through the bid {} -- dollars instead of cents?",
+                                 order.price, tolerance * 100.0, touch) );
+        }
+        None
+    }
+
+    /// Ok if `order` passes (or only gets a warning), otherwise `StockfighterErr::Rejected`.
+    pub fn check( &self, order: &Order ) -> Result<(), StockfighterErr> {
+        self.assert_units( order );
+        self.verdict( self.problem( order ) )
+    }
+
+    /// As `check`, also applying `limit_through` against `quote`.
+    pub fn check_against( &self, order: &Order, quote: &Quote ) -> Result<(), StockfighterErr> {
+        self.assert_units( order );
+        self.verdict( self.problem_against( order, quote ) )
+    }
+
+    // With `audit` on, an order outside its band is a bug in the bot, not a bad order
+    fn assert_units( &self, order: &Order ) {
+        if self.audit {
+            if let Some( why ) = self.band_problem( order ) {
+                panic!("units audit: {}", why);
+            }
+        }
+    }
+
+    fn verdict( &self, problem: Option<String> ) -> Result<(), StockfighterErr> {
+        match problem {
+            Some( why ) => {
+                if self.warn_only {
+                    let _ = writeln!( io::stderr(), "pre-trade warning: {}", why );
+                    Ok( () )
+                } else {
+                    Err( StockfighterErr::Rejected( why ) )
+                }
+            },
+            None => Ok( () ),
+        }
+    }
+
+    /// Checks `order` and sends it if it passes.
+    pub fn submit( &self, order: &Order, auth: &Auth ) -> Result<OrderResponse, StockfighterErr> {
+        try!( self.check( order ) );
+        order.process_order_with( auth )
+    }
+
+    /// Checks `order` against `quote` and sends it if it passes.
+    pub fn submit_against( &self, order: &Order, quote: &Quote, auth: &Auth ) -> Result<OrderResponse, StockfighterErr> {
+        try!( self.check_against( order, quote ) );
+        order.process_order_with( auth )
+    }
+}
@@ -0,0 +1,84 @@
+// Prices on the wire are integer cents. People type (and like to read) dollars, so these
+// convert between the two without ever going through a float.
+
+use StockfighterErr;
+
+/// Parses a human price ("25.30", "25.3", "$25.30", "25", "-0.50") into cents.
+///
+/// Anything finer than a cent is an error rather than being rounded away.
+///
+/// # Example
+/// ```
+/// use market::price::parse_price;
+/// assert_eq!( parse_price( "25.30" ).unwrap(), 2530 );
+/// assert_eq!( parse_price( "$25.3" ).unwrap(), 2530 );
+/// assert_eq!( parse_price( "25" ).unwrap(), 2500 );
+/// assert!( parse_price( "25.305" ).is_err() );
+/// assert!( parse_price( "twenty" ).is_err() );
+/// ```
+///
+/// Malformed or out-of-range input is always `BadPrice`, never a panic or a wrapped value:
+///
+/// ```
+/// use market::StockfighterErr;
+/// use market::price::parse_price;
+/// let junk = [ "", " ", "-", "$", ".", "-.", "$.", "--1", "$-1", "1.2.3", "1..2", "1.-2",
+///              "+1", "1e3", "0x10", "1,000", "21474836.48", "99999999999999999999", "1.٣",
+///              "\u{0}", "１２" ];
+/// for text in junk.iter() {
+///     match parse_price( text ) {
+///         Err( StockfighterErr::BadPrice( ref bad ) ) => assert_eq!( bad, *text ),
+///         other => panic!( "{:?} parsed as {:?}", text, other.ok() ),
+///     }
+/// }
+/// assert_eq!( parse_price( "21474836.47" ).unwrap(), ::std::i32::MAX );
+/// ```
+pub fn parse_price( text: &str ) -> Result<i32, StockfighterErr> {
+    let bad = || StockfighterErr::BadPrice( text.to_owned() );
+    let mut rest = text.trim();
+    let negative = rest.starts_with( '-' );
+    if negative {
+        rest = &rest[1..];
+    }
+    if rest.starts_with( '$' ) {
+        rest = &rest[1..];
+    }
+
+    let mut parts = rest.splitn( 2, '.' );
+    let whole = parts.next().unwrap_or( "" );
+    let fraction = parts.next().unwrap_or( "" );
+    if whole.is_empty() && fraction.is_empty() {
+        return Err( bad() );
+    }
+    if !whole.chars().all( |c| c.is_digit( 10 ) ) || !fraction.chars().all( |c| c.is_digit( 10 ) ) {
+        return Err( bad() );
+    }
+    if fraction.len() > 2 {
+        return Err( bad() );
+    }
+
+    let dollars: i32 = if whole.is_empty() { 0 } else { try!( whole.parse().map_err( |_| bad() ) ) };
+    let mut cents: i32 = if fraction.is_empty() { 0 } else { try!( fraction.parse().map_err( |_| bad() ) ) };
+    // "25.3" means 30 cents, not 3
+    if fraction.len() == 1 {
+        cents *= 10;
+    }
+    let total = try!( dollars.checked_mul( 100 )
+                             .and_then( |d| d.checked_add( cents ) )
+                             .ok_or_else( bad ) );
+    Ok( if negative { -total } else { total } )
+}
+
+/// Formats cents as dollars with exactly two decimal places.
+///
+/// # Example
+/// ```
+/// use market::price::format_price;
+/// assert_eq!( format_price( 2530 ), "25.30" );
+/// assert_eq!( format_price( 5 ), "0.05" );
+/// assert_eq!( format_price( -50 ), "-0.50" );
+/// ```
+pub fn format_price( cents: i32 ) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, ( cents / 100 ).abs(), ( cents % 100 ).abs())
+}
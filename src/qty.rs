@@ -0,0 +1,89 @@
+// Share counts.
+//
+// An order, fill, book level or quote size can't be for a negative number of shares, but as an
+// i32 nothing stopped one from being built and sent. Qty wraps a u32 so that can't happen, and
+// only offers checked / saturating arithmetic so it can't quietly wrap either. Signed numbers are
+// kept for the one place they mean something: net positions (long is positive, short negative).
+
+use serde;
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Qty( pub u32 );
+
+impl Qty {
+    pub fn get( self ) -> u32 {
+        self.0
+    }
+
+    pub fn is_zero( self ) -> bool {
+        self.0 == 0
+    }
+
+    /// The size of a signed net quantity, eg. how many shares it takes to flatten a position.
+    ///
+    /// # Example
+    /// ```
+    /// use market::Qty;
+    /// assert_eq!( Qty::magnitude( -300 ), Qty( 300 ) );
+    /// ```
+    pub fn magnitude( net: i32 ) -> Qty {
+        Qty( ( net as i64 ).abs() as u32 )
+    }
+
+    /// None for a negative count.
+    pub fn from_signed( qty: i32 ) -> Option<Qty> {
+        if qty < 0 { None } else { Some( Qty( qty as u32 ) ) }
+    }
+
+    /// This many shares as a change in net position: positive when buying, negative when
+    /// selling. Saturates at the ends of i32.
+    pub fn signed( self, buy: bool ) -> i32 {
+        let magnitude = if self.0 > i32::max_value() as u32 { i32::max_value() } else { self.0 as i32 };
+        if buy { magnitude } else { -magnitude }
+    }
+
+    pub fn checked_add( self, other: Qty ) -> Option<Qty> {
+        self.0.checked_add( other.0 ).map( Qty )
+    }
+
+    /// None if `other` is bigger -- a negative share count.
+    pub fn checked_sub( self, other: Qty ) -> Option<Qty> {
+        self.0.checked_sub( other.0 ).map( Qty )
+    }
+
+    pub fn saturating_add( self, other: Qty ) -> Qty {
+        Qty( self.0.saturating_add( other.0 ) )
+    }
+
+    pub fn saturating_sub( self, other: Qty ) -> Qty {
+        Qty( self.0.saturating_sub( other.0 ) )
+    }
+}
+
+impl From<u32> for Qty {
+    fn from( qty: u32 ) -> Qty {
+        Qty( qty )
+    }
+}
+
+impl fmt::Display for Qty {
+    fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+        // Delegating keeps width / alignment flags working
+        fmt::Display::fmt( &self.0, f )
+    }
+}
+
+impl serde::Serialize for Qty {
+    fn serialize<S>( &self, serializer: &mut S ) -> Result<(), S::Error> where S: serde::Serializer {
+        serializer.serialize_u32( self.0 )
+    }
+}
+
+// A negative number from the wire fails to deserialize rather than becoming a Qty
+impl serde::Deserialize for Qty {
+    fn deserialize<D>( deserializer: &mut D ) -> Result<Qty, D::Error> where D: serde::Deserializer {
+        u32::deserialize( deserializer ).map( Qty )
+    }
+}
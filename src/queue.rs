@@ -0,0 +1,303 @@
+// Rate-limited order submission, most urgent first.
+//
+// Venues throttle order traffic, so a bot that sends everything the moment it decides to
+// soon finds itself queueing -- and in a plain queue a cancel waits behind the new orders
+// that were sent before it. That's backwards: pulling a stale quote or flattening a position
+// matters more than adding another one. OrderQueue keeps a lane per priority and, whenever
+// the rate allows another request, sends from the most urgent non-empty lane.
+//
+// Nothing is sent until `pump` is called, so the queue fits into the same loop as the rest of
+// the strategy (see scheduler::Scheduler).
+//
+// A venue whose heartbeat says it's wedged won't take orders anyway. Mark it (by hand or from
+// a health::HealthReport) and, by default, its jobs are held in the queue -- up to a bound --
+// while everything else keeps flowing, then sent once it recovers. With WedgePolicy::FailFast
+// they're handed back straight away with `VenueWedged` instead.
+//
+// Given a shared budget::RateBudget, new orders spend from its write budget and cancels and
+// flattening orders may also use its emergency reserve, on top of the queue's own pacing.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use {Auth, Order, OrderResponse, StockfighterErr};
+use budget::{Class, RateBudget};
+use clock::{Clock, SystemClock};
+use health::{Health, HealthReport};
+
+/// Most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Cancelling resting orders
+    Cancel,
+    /// Orders that reduce risk: flattening, stop-outs
+    Flatten,
+    /// Everything else
+    New,
+}
+
+static PRIORITIES: [Priority; 3] = [ Priority::Cancel, Priority::Flatten, Priority::New ];
+
+#[derive(Debug, Clone)]
+pub enum Job {
+    Submit( Order ),
+    Cancel( OrderResponse ),
+}
+
+impl Job {
+    pub fn venue( &self ) -> &str {
+        match *self {
+            Job::Submit( ref order ) => &order.venue,
+            Job::Cancel( ref order ) => &order.venue,
+        }
+    }
+}
+
+/// What happens to jobs for a wedged venue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WedgePolicy {
+    /// Keep them queued until the venue recovers. Past `max` held jobs, the least urgent,
+    /// oldest ones are failed with `VenueWedged` to make room.
+    Hold { max: usize },
+    /// Fail them with `VenueWedged` as soon as they come up.
+    FailFast,
+}
+
+/// A job that has been sent, and how it went.
+#[derive(Debug)]
+pub struct Sent {
+    pub priority: Priority,
+    pub job: Job,
+    pub result: Result<OrderResponse, StockfighterErr>,
+}
+
+pub struct OrderQueue {
+    lanes: Vec<VecDeque<Job>>,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    auth: Auth,
+    clock: Arc<Clock>,
+    wedged: HashSet<String>,
+    wedge_policy: WedgePolicy,
+    budget: Option<Arc<RateBudget>>,
+}
+
+impl OrderQueue {
+    /// A queue that sends at most 10 requests a second.
+    ///
+    /// # Example
+    /// ```
+    /// use market::queue::OrderQueue;
+    /// let mut queue = OrderQueue::new().per_second( 5.0 );
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// queue.submit( order );
+    /// while !queue.is_empty() {
+    ///   for sent in queue.pump() {
+    ///     println!("{:?} -> {:?}", sent.priority, sent.result.map( |response| response.id ) );
+    ///   }
+    /// }
+    /// ```
+    pub fn new() -> OrderQueue {
+        OrderQueue {
+            lanes: PRIORITIES.iter().map( |_| VecDeque::new() ).collect(),
+            interval: Duration::from_millis( 100 ),
+            last_sent: None,
+            auth: Auth::Env,
+            clock: Arc::new( SystemClock ),
+            wedged: HashSet::new(),
+            wedge_policy: WedgePolicy::Hold { max: 100 },
+            budget: None,
+        }
+    }
+
+    /// Sends at most `rate` requests a second, cancels included.
+    pub fn per_second( mut self, rate: f64 ) -> OrderQueue {
+        let nanos = if rate > 0.0 { ( 1e9 / rate ) as u64 } else { 0 };
+        self.interval = Duration::new( nanos / 1_000_000_000, ( nanos % 1_000_000_000 ) as u32 );
+        self
+    }
+
+    pub fn with_auth( mut self, auth: Auth ) -> OrderQueue {
+        self.auth = auth;
+        self
+    }
+
+    /// Paces requests with `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> OrderQueue {
+        self.clock = clock;
+        self
+    }
+
+    /// Also spends from `budget`: Class::Write for new orders, Class::Emergency for cancels
+    /// and flattening orders.
+    pub fn budget( mut self, budget: Arc<RateBudget> ) -> OrderQueue {
+        self.budget = Some( budget );
+        self
+    }
+
+    /// What to do with jobs for wedged venues; holds up to 100 by default.
+    pub fn on_wedge( mut self, policy: WedgePolicy ) -> OrderQueue {
+        self.wedge_policy = policy;
+        self
+    }
+
+    /// Marks `venue` as wedged (or recovered).
+    pub fn set_wedged( &mut self, venue: &str, wedged: bool ) {
+        if wedged {
+            self.wedged.insert( venue.to_owned() );
+        } else {
+            self.wedged.remove( venue );
+        }
+    }
+
+    /// Takes venue states from a heartbeat round: a venue that answered ok: false is wedged,
+    /// one that answered ok has recovered. Unreachable venues are left as they were -- that's
+    /// the network, not the venue.
+    ///
+    /// # Example
+    /// ```
+    /// use market::queue::{OrderQueue, WedgePolicy};
+    /// let mut queue = OrderQueue::new().on_wedge( WedgePolicy::Hold { max: 20 } );
+    /// let report = market::health::heartbeat_all( &[ "TESTEX".to_owned() ] );
+    /// queue.observe( &report );
+    /// if queue.is_wedged( "TESTEX" ) {
+    ///   println!("holding {} jobs for TESTEX", queue.held() );
+    /// }
+    /// ```
+    pub fn observe( &mut self, report: &HealthReport ) {
+        for ( venue, health ) in &report.venues {
+            match *health {
+                Health::Up => self.set_wedged( venue, false ),
+                Health::Down( _ ) => self.set_wedged( venue, true ),
+                Health::Unreachable( _ ) => {},
+            }
+        }
+    }
+
+    pub fn is_wedged( &self, venue: &str ) -> bool {
+        self.wedged.contains( venue )
+    }
+
+    /// How many queued jobs are waiting on a wedged venue.
+    pub fn held( &self ) -> usize {
+        self.lanes.iter()
+            .map( |lane| lane.iter().filter( |job| self.wedged.contains( job.venue() ) ).count() )
+            .sum()
+    }
+
+    pub fn push( &mut self, priority: Priority, job: Job ) {
+        self.lanes[ priority as usize ].push_back( job );
+    }
+
+    /// Queues a new order at normal priority.
+    pub fn submit( &mut self, order: Order ) {
+        self.push( Priority::New, Job::Submit( order ) );
+    }
+
+    /// Queues a risk-reducing order, ahead of every new order.
+    pub fn flatten( &mut self, order: Order ) {
+        self.push( Priority::Flatten, Job::Submit( order ) );
+    }
+
+    /// Queues a cancel, ahead of everything else.
+    pub fn cancel( &mut self, order: OrderResponse ) {
+        self.push( Priority::Cancel, Job::Cancel( order ) );
+    }
+
+    /// Drops every queued new order, e.g. when the strategy is pulling out.
+    pub fn clear_new( &mut self ) -> Vec<Job> {
+        self.lanes[ Priority::New as usize ].drain( .. ).collect()
+    }
+
+    pub fn len( &self ) -> usize {
+        self.lanes.iter().map( |lane| lane.len() ).sum()
+    }
+
+    pub fn is_empty( &self ) -> bool {
+        self.len() == 0
+    }
+
+    /// How many jobs are waiting at `priority`.
+    pub fn pending( &self, priority: Priority ) -> usize {
+        self.lanes[ priority as usize ].len()
+    }
+
+    /// When the rate next allows a request; None if one could go now.
+    pub fn next_slot( &self ) -> Option<Instant> {
+        let now = self.clock.now();
+        match self.last_sent {
+            Some( last ) if last + self.interval > now => Some( last + self.interval ),
+            _ => None,
+        }
+    }
+
+    // Where the first job (most urgent lane first, or least urgent with `reverse`) whose venue
+    // is or isn't wedged sits
+    fn find( &self, wedged: bool, reverse: bool ) -> Option<( Priority, usize )> {
+        let mut order: Vec<Priority> = PRIORITIES.to_vec();
+        if reverse {
+            order.reverse();
+        }
+        for priority in order {
+            let lane = &self.lanes[ priority as usize ];
+            if let Some( index ) = lane.iter().position( |job| self.wedged.contains( job.venue() ) == wedged ) {
+                return Some( ( priority, index ) );
+            }
+        }
+        None
+    }
+
+    // A job the wedge policy says to give up on, failed without using up a rate slot
+    fn shed( &mut self ) -> Option<Sent> {
+        if self.wedged.is_empty() {
+            return None;
+        }
+        let found = match self.wedge_policy {
+            WedgePolicy::FailFast => self.find( true, false ),
+            WedgePolicy::Hold { max } if self.held() > max => self.find( true, true ),
+            WedgePolicy::Hold { .. } => None,
+        };
+        let ( priority, index ) = match found {
+            Some( found ) => found,
+            None => return None,
+        };
+        self.lanes[ priority as usize ].remove( index ).map( |job| {
+            let venue = job.venue().to_owned();
+            Sent { priority: priority, job: job, result: Err( StockfighterErr::VenueWedged( venue ) ) }
+        })
+    }
+
+    /// Sends the most urgent queued job if the rate allows one now, skipping jobs held for a
+    /// wedged venue. Returns at most one result; call it from the strategy loop.
+    pub fn pump( &mut self ) -> Option<Sent> {
+        if let Some( shed ) = self.shed() {
+            return Some( shed );
+        }
+        if self.next_slot().is_some() {
+            return None;
+        }
+        let ( priority, index ) = match self.find( false, false ) {
+            Some( found ) => found,
+            None => return None,
+        };
+        if let Some( ref budget ) = self.budget {
+            let class = if priority == Priority::New { Class::Write } else { Class::Emergency };
+            if !budget.try_take( class ) {
+                return None;
+            }
+        }
+        let job = match self.lanes[ priority as usize ].remove( index ) {
+            Some( job ) => job,
+            None => return None,
+        };
+        self.last_sent = Some( self.clock.now() );
+        let result = match job {
+            Job::Submit( ref order ) => order.process_order_with( &self.auth ),
+            Job::Cancel( ref order ) => order.cancel_with( &self.auth ),
+        };
+        Some( Sent { priority: priority, job: job, result: result } )
+    }
+}
@@ -0,0 +1,202 @@
+// Standing two-sided quotes.
+//
+// The mechanical core of market making: keep exactly one resting bid and one resting ask at
+// the prices and sizes the strategy wants. The strategy only says where it wants to be, with
+// `set_bid` / `set_ask`; `sync` looks at what's actually resting and fixes the difference --
+// posting a missing side, cancel/replacing a side whose price changed, and topping a side back
+// up to full size after it has been partially filled. Fills seen along the way are handed
+// back so the strategy can update its inventory -- and if a sync fails part way, the fills it
+// had already seen wait for the next successful one rather than being lost with the error.
+
+use {Auth, Order, OrderFill, OrderResponse, Qty, StockfighterErr};
+
+/// Where one side of the pair should rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub price: i32,
+    pub qty: Qty,
+}
+
+/// A fill on one of the pair's orders.
+#[derive(Debug, Clone)]
+pub struct PairFill {
+    /// "buy" or "sell"
+    pub direction: String,
+    pub order_id: i32,
+    pub fill: OrderFill,
+}
+
+#[derive(Debug, Clone)]
+struct Side {
+    buy: bool,
+    target: Option<Target>,
+    resting: Option<OrderResponse>,
+    // How many of `resting`'s fills have already been reported
+    reported: usize,
+}
+
+impl Side {
+    fn new( buy: bool ) -> Side {
+        Side { buy: buy, target: None, resting: None, reported: 0 }
+    }
+
+    fn direction( &self ) -> &'static str {
+        if self.buy { "buy" } else { "sell" }
+    }
+
+    // Adopts the latest known state of the resting order, reporting any new fills
+    fn absorb( &mut self, latest: OrderResponse, fills: &mut Vec<PairFill> ) {
+        for fill in latest.fills.iter().skip( self.reported ) {
+            fills.push( PairFill {
+                direction: self.direction().to_owned(),
+                order_id: latest.id,
+                fill: fill.clone(),
+            });
+        }
+        if latest.open {
+            self.reported = latest.fills.len();
+            self.resting = Some( latest );
+        } else {
+            self.reported = 0;
+            self.resting = None;
+        }
+    }
+}
+
+pub struct QuotePair {
+    account: String,
+    venue: String,
+    stock: String,
+    auth: Auth,
+    bid: Side,
+    ask: Side,
+    // Fills seen but not yet handed back, because the sync that saw them failed
+    pending: Vec<PairFill>,
+}
+
+impl QuotePair {
+    /// A pair with nothing resting and no targets yet.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use market::Qty;
+    /// use market::quotepair::{QuotePair, Target};
+    /// let client = market::StockfighterClient::new();
+    /// let mut pair = QuotePair::new( "EXB123456".to_owned(), "TESTEX".to_owned(), "FOOBAR".to_owned() );
+    /// let mut position = 0;
+    /// loop {
+    ///   let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    ///   if quote.bid <= 0 || quote.ask <= 0 { continue; }
+    ///   let mid = ( quote.bid + quote.ask ) / 2;
+    ///   pair.set_bid( Some( Target { price: mid - 10, qty: Qty( 100 ) } ) );
+    ///   pair.set_ask( Some( Target { price: mid + 10, qty: Qty( 100 ) } ) );
+    ///   for fill in pair.sync().unwrap() {
+    ///     position += fill.fill.qty.signed( fill.direction == "buy" );
+    ///   }
+    ///   if position.abs() > 1000 { break; }
+    /// }
+    /// pair.cancel_all().unwrap();
+    /// ```
+    pub fn new( account: String, venue: String, stock: String ) -> QuotePair {
+        QuotePair {
+            account: account,
+            venue: venue,
+            stock: stock,
+            auth: Auth::Env,
+            bid: Side::new( true ),
+            ask: Side::new( false ),
+            pending: vec![],
+        }
+    }
+
+    /// Authenticates every order, status check and cancel with `auth`.
+    pub fn with_auth( mut self, auth: Auth ) -> QuotePair {
+        self.auth = auth;
+        self
+    }
+
+    /// Where the bid should rest, or None to have no bid. Takes effect on the next `sync`.
+    pub fn set_bid( &mut self, target: Option<Target> ) {
+        self.bid.target = target;
+    }
+
+    /// Where the ask should rest, or None to have no ask. Takes effect on the next `sync`.
+    pub fn set_ask( &mut self, target: Option<Target> ) {
+        self.ask.target = target;
+    }
+
+    /// The bid order currently resting, as of the last `sync`.
+    pub fn resting_bid( &self ) -> Option<&OrderResponse> {
+        self.bid.resting.as_ref()
+    }
+
+    /// The ask order currently resting, as of the last `sync`.
+    pub fn resting_ask( &self ) -> Option<&OrderResponse> {
+        self.ask.resting.as_ref()
+    }
+
+    /// Brings both sides in line with their targets, returning fills seen since the last
+    /// successful sync. If one side fails the other is still synced and the first error is
+    /// returned; the fills seen so far are kept and come back from the next sync that succeeds.
+    pub fn sync( &mut self ) -> Result<Vec<PairFill>, StockfighterErr> {
+        let bid = sync_side( &mut self.bid, &self.account, &self.venue, &self.stock, &self.auth, &mut self.pending );
+        let ask = sync_side( &mut self.ask, &self.account, &self.venue, &self.stock, &self.auth, &mut self.pending );
+        try!( bid );
+        try!( ask );
+        Ok( ::std::mem::replace( &mut self.pending, vec![] ) )
+    }
+
+    /// Fills seen by a failed sync, waiting to be handed back by the next successful one.
+    pub fn pending_fills( &self ) -> &[PairFill] {
+        &self.pending
+    }
+
+    /// Clears both targets and cancels whatever is resting, returning the last fills.
+    pub fn cancel_all( &mut self ) -> Result<Vec<PairFill>, StockfighterErr> {
+        self.bid.target = None;
+        self.ask.target = None;
+        self.sync()
+    }
+}
+
+fn sync_side( side: &mut Side, account: &str, venue: &str, stock: &str, auth: &Auth,
+              fills: &mut Vec<PairFill> ) -> Result<(), StockfighterErr> {
+    if let Some( resting ) = side.resting.clone() {
+        let latest = try!( resting.status_with( auth ) );
+        side.absorb( latest, fills );
+    }
+    // Anything still resting that isn't exactly the target -- wrong price, or short after a
+    // partial fill -- is cancelled, to be replaced below
+    let stale = match ( side.resting.as_ref(), side.target ) {
+        ( Some( resting ), Some( target ) ) => resting.price != target.price || resting.qty != target.qty,
+        ( Some( _ ), None ) => true,
+        ( None, _ ) => false,
+    };
+    if stale {
+        if let Some( resting ) = side.resting.clone() {
+            let last = try!( resting.cancel_with( auth ) );
+            side.absorb( last, fills );
+            // A cancel that raced the final fills can come back still marked open; either way
+            // it's not resting any more
+            side.resting = None;
+            side.reported = 0;
+        }
+    }
+    if side.resting.is_none() {
+        if let Some( target ) = side.target {
+            if target.qty.is_zero() {
+                return Ok( () );
+            }
+            let order = Order::new( account.to_owned(),
+                                    venue.to_owned(),
+                                    stock.to_owned(),
+                                    target.price,
+                                    target.qty,
+                                    side.direction().to_owned(),
+                                    "limit".to_owned() );
+            let response = try!( order.process_order_with( auth ) );
+            side.absorb( response, fills );
+        }
+    }
+    Ok( () )
+}
@@ -0,0 +1,110 @@
+// Deliberately unvalidated requests, for poking at the server's edge cases.
+//
+// Everything else in the library goes out of its way to only send well formed requests. This
+// module does the opposite: the path, headers and body are sent exactly as given, so tests can
+// throw odd JSON, bogus order types or missing headers at any endpoint and see what comes back.
+// The response is captured whole (status, headers, body) whether or not it parses.
+
+use hyper::Client;
+use hyper::header::Headers;
+use hyper::method::Method;
+use serde;
+use serde_json;
+
+use std::io::Read;
+
+use decode;
+use {Auth, StockfighterErr, STOCKFIGHTER_API_URL};
+
+#[derive(Debug, Clone)]
+pub struct RawRequest {
+    pub method: Method,
+    /// Appended as-is to the API base URL. Nothing is encoded or checked.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// Authentication to add when sending, if any
+    pub auth: Option<Auth>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RawRequest {
+    pub fn new( method: Method, path: String ) -> RawRequest {
+        RawRequest {
+            method: method,
+            path: path,
+            headers: vec![],
+            body: None,
+            auth: None,
+        }
+    }
+
+    /// Adds the usual API key header, for when the point of the test is the body rather than
+    /// the authentication.
+    pub fn with_auth( mut self ) -> RawRequest {
+        self.auth = Some( Auth::Env );
+        self
+    }
+
+    /// Sends the request exactly as built.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate hyper;
+    /// extern crate market;
+    /// use market::raw::RawRequest;
+    /// use hyper::method::Method;
+    /// # fn main() {
+    /// // An order type the server has never heard of
+    /// let mut request = RawRequest::new( Method::Post,
+    ///                                    "/venues/TESTEX/stocks/FOOBAR/orders".to_owned() )
+    ///                       .with_auth();
+    /// request.body = Some( r#"{"orderType":"sideways","qty":-1}"#.to_owned() );
+    /// let response = request.send().unwrap();
+    /// println!("{} {}", response.status, response.body );
+    /// # }
+    /// ```
+    pub fn send( &self ) -> Result<RawResponse, StockfighterErr> {
+        let url = format!("{}{}", STOCKFIGHTER_API_URL, self.path);
+        let mut headers = Headers::new();
+        if let Some( ref auth ) = self.auth {
+            try!( auth.apply( &mut headers ) );
+        }
+        for &( ref name, ref value ) in &self.headers {
+            headers.set_raw( name.clone(), vec![ value.as_bytes().to_vec() ] );
+        }
+        let client = Client::new();
+        let mut request = client.request( self.method.clone(), &url ).headers( headers );
+        if let Some( ref body ) = self.body {
+            request = request.body( body.as_str() );
+        }
+        let mut response = try!( request.send() );
+        let mut body = String::new();
+        try!( response.read_to_string( &mut body ) );
+        Ok( RawResponse {
+            status: response.status.to_u16(),
+            headers: response.headers.iter()
+                             .map( |header| ( header.name().to_owned(), header.value_string() ) )
+                             .collect(),
+            body: body,
+        })
+    }
+}
+
+impl RawResponse {
+    /// The body as untyped JSON, for responses that don't fit any of the library's structs.
+    pub fn json( &self ) -> Result<serde_json::Value, StockfighterErr> {
+        decode::decode( &self.body )
+    }
+
+    /// The body parsed as one of the library's structs (eg. `OrderResponse`).
+    pub fn parse<T: serde::Deserialize>( &self ) -> Result<T, StockfighterErr> {
+        decode::decode( &self.body )
+    }
+}
@@ -0,0 +1,365 @@
+// Files for recorded data.
+//
+// A night's worth of quotes, fills and book deltas as JSON lines runs to gigabytes, and
+// compresses about tenfold. `create` writes gzip when the path ends in ".gz" and plain text
+// otherwise; `open` reads either, telling them apart by the gzip magic bytes rather than the
+// name, so a replayer never needs to know how a file was written.
+//
+// Gzip needs the `gzip` feature (it pulls in flate2). Without it, ".gz" paths are an error
+// rather than a silently uncompressed file with a misleading name.
+//
+// Files from a crashed run end mid-line. SequencedWriter numbers and checksums every record
+// and signs off with a footer, so `verify` can tell a complete recording from a damaged one
+// and hand back everything up to the damage.
+//
+// Recordings of market::event::MarketEvent -- quotes, trades, fills and the rest in one stream
+// -- are written with `write_event` (or one JSON event per line) and fed back with `replay`.
+
+#[cfg(feature = "gzip")]
+use flate2;
+use serde;
+use serde_json;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use StockfighterErr;
+use decode;
+use event::MarketEvent;
+
+static GZIP_MAGIC: [u8; 2] = [ 0x1f, 0x8b ];
+
+fn is_gzip_path( path: &Path ) -> bool {
+    path.extension().map_or( false, |extension| extension == "gz" )
+}
+
+fn no_gzip() -> StockfighterErr {
+    StockfighterErr::IO( io::Error::new( io::ErrorKind::Other,
+                                         "gzip support not built in (enable the `gzip` feature)" ) )
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_writer( file: BufWriter<File> ) -> Result<Box<Write + Send>, StockfighterErr> {
+    Ok( Box::new( flate2::write::GzEncoder::new( file, flate2::Compression::Default ) ) )
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_writer( _: BufWriter<File> ) -> Result<Box<Write + Send>, StockfighterErr> {
+    Err( no_gzip() )
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_reader( file: BufReader<File> ) -> Result<Box<BufRead + Send>, StockfighterErr> {
+    let decoder = try!( flate2::read::GzDecoder::new( file ) );
+    Ok( Box::new( BufReader::new( decoder ) ) )
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_reader( _: BufReader<File> ) -> Result<Box<BufRead + Send>, StockfighterErr> {
+    Err( no_gzip() )
+}
+
+/// Creates (or truncates) `path` for writing, gzipped if it ends in ".gz". The gzip trailer is
+/// written when the writer is dropped, so drop it before reading the file back.
+///
+/// # Example
+/// ```
+/// use std::io::Write;
+/// use std::path::Path;
+/// let mut out = market::recording::create( Path::new( "quotes.jsonl.gz" ) ).unwrap();
+/// writeln!( out, r#"{{"symbol":"FOOBAR","bid":5000,"ask":5010}}"# ).unwrap();
+/// ```
+pub fn create( path: &Path ) -> Result<Box<Write + Send>, StockfighterErr> {
+    let file = BufWriter::new( try!( File::create( path ) ) );
+    if is_gzip_path( path ) {
+        gzip_writer( file )
+    } else {
+        Ok( Box::new( file ) )
+    }
+}
+
+/// Opens `path` for reading line by line, decompressing it if it's gzipped.
+///
+/// # Example
+/// ```
+/// use std::io::BufRead;
+/// use std::path::Path;
+/// let recorded = market::recording::open( Path::new( "quotes.jsonl.gz" ) ).unwrap();
+/// for line in recorded.lines() {
+///   let quote: market::Quote = market::decode::decode( &line.unwrap() ).unwrap();
+///   println!("{} / {}", quote.bid, quote.ask );
+/// }
+/// ```
+pub fn open( path: &Path ) -> Result<Box<BufRead + Send>, StockfighterErr> {
+    let mut file = BufReader::new( try!( File::open( path ) ) );
+    let gzipped = {
+        let start = try!( file.fill_buf() );
+        start.len() >= 2 && start[ 0 ] == GZIP_MAGIC[ 0 ] && start[ 1 ] == GZIP_MAGIC[ 1 ]
+    };
+    if gzipped {
+        gzip_reader( file )
+    } else {
+        Ok( Box::new( file ) )
+    }
+}
+
+// FNV-1a, 64 bit. Not cryptographic -- it only has to notice torn writes and bit rot
+fn fnv1a( hash: u64, bytes: &[u8] ) -> u64 {
+    let mut hash = hash;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul( 0x100000001b3 );
+    }
+    hash
+}
+
+static FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// The 64 bit FNV-1a checksum of `bytes` as 16 hex digits -- the checksum used throughout
+/// recorded files. Good for spotting damage and mix-ups, not for proving anything to an
+/// adversary.
+///
+/// # Example
+/// ```
+/// assert_eq!( market::recording::checksum( b"" ), "cbf29ce484222325" );
+/// ```
+pub fn checksum( bytes: &[u8] ) -> String {
+    format!("{:016x}", fnv1a( FNV_OFFSET, bytes ) )
+}
+
+/// Writes records as JSON lines, each wrapped with a sequence number and a checksum, and
+/// closes the file with a footer checksumming the whole run:
+///
+/// ```text
+/// {"seq":1,"fnv":"9f2c...","record":{...}}
+/// {"seq":2,"fnv":"41d0...","record":{...}}
+/// {"seq":2,"footer":"7a33..."}
+/// ```
+///
+/// A file without the footer was cut short -- a crash, a full disk -- and `verify` says so,
+/// along with how far the intact part goes.
+pub struct SequencedWriter {
+    out: Box<Write + Send>,
+    seq: u64,
+    rolling: u64,
+}
+
+impl SequencedWriter {
+    /// # Example
+    /// ```
+    /// use std::path::Path;
+    /// use market::recording::{self, SequencedWriter};
+    /// let client = market::StockfighterClient::new();
+    /// let mut out = SequencedWriter::new( recording::create( Path::new( "quotes.jsonl" ) ).unwrap() );
+    /// for _ in 0..100 {
+    ///   out.write( &client.quote( "TESTEX", "FOOBAR" ).unwrap() ).unwrap();
+    /// }
+    /// out.finish().unwrap();
+    /// ```
+    pub fn new( out: Box<Write + Send> ) -> SequencedWriter {
+        SequencedWriter { out: out, seq: 0, rolling: FNV_OFFSET }
+    }
+
+    /// Serializes and writes `record`, returning its sequence number.
+    pub fn write<T: serde::Serialize>( &mut self, record: &T ) -> Result<u64, StockfighterErr> {
+        let json = try!( serde_json::to_string( record ) );
+        self.write_json( &json )
+    }
+
+    /// Writes `event` as a record, for `replay` or `Verified::events` to read back.
+    pub fn write_event( &mut self, event: &MarketEvent ) -> Result<u64, StockfighterErr> {
+        self.write( event )
+    }
+
+    /// Writes an already serialized record. It must be a single line of JSON.
+    pub fn write_json( &mut self, json: &str ) -> Result<u64, StockfighterErr> {
+        self.seq += 1;
+        let sum = fnv1a( FNV_OFFSET, json.as_bytes() );
+        self.rolling = fnv1a( self.rolling, format!("{:016x}", sum ).as_bytes() );
+        try!( writeln!( self.out, r#"{{"seq":{},"fnv":"{:016x}","record":{}}}"#, self.seq, sum, json ) );
+        Ok( self.seq )
+    }
+
+    /// Writes the footer and flushes. Until this is called the file reads as truncated.
+    pub fn finish( mut self ) -> Result<(), StockfighterErr> {
+        try!( writeln!( self.out, r#"{{"seq":{},"footer":"{:016x}"}}"#, self.seq, self.rolling ) );
+        try!( self.out.flush() );
+        Ok( () )
+    }
+}
+
+/// What's wrong with a sequenced file, past its last good record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Damage {
+    /// No footer: the writer never finished
+    Truncated,
+    /// A line that isn't a sequenced record (often the half-written last line), isn't valid
+    /// UTF-8, or couldn't be read at all (a compressed stream cut off part way)
+    Unreadable { line: usize },
+    /// A record whose checksum doesn't match its contents
+    BadChecksum { seq: u64 },
+    /// A record out of order -- lost or duplicated lines
+    OutOfSequence { expected: u64, found: u64 },
+    /// The footer doesn't agree with the records before it
+    BadFooter,
+}
+
+/// The result of reading a sequenced file: every record up to the first problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified {
+    /// The JSON of each intact record, in order
+    pub records: Vec<String>,
+    /// Sequence number of the last intact record, 0 if there are none
+    pub last_good: u64,
+    /// None if the file is complete and intact
+    pub damage: Option<Damage>,
+}
+
+impl Verified {
+    /// The intact records as events, for a file written with `write_event`.
+    pub fn events( &self ) -> Result<Vec<MarketEvent>, StockfighterErr> {
+        self.records.iter().map( |json| decode::decode( json ) ).collect()
+    }
+}
+
+// Splits `{"seq":N,"fnv":"H","record":R}` into (N, H, R) without reformatting R, so the
+// checksum is taken over exactly the bytes that were written
+fn split_record( line: &str ) -> Option<(u64, &str, &str)> {
+    let line = line.trim_right();
+    if !line.starts_with( r#"{"seq":"# ) {
+        return None;
+    }
+    let rest = &line[ 7.. ];
+    let comma = match rest.find( ',' ) { Some( comma ) => comma, None => return None };
+    let seq = match rest[ ..comma ].parse() { Ok( seq ) => seq, Err( _ ) => return None };
+    let rest = &rest[ comma.. ];
+    // `,"fnv":"` + 16 hex digits + `","record":` + at least one byte of record + `}`
+    if rest.len() < 37 || !rest.starts_with( r#","fnv":""# ) || !rest.ends_with( '}' ) {
+        return None;
+    }
+    // A corrupted line can put a multi-byte character anywhere
+    if !rest.is_char_boundary( 24 ) || !rest.is_char_boundary( 35 ) || &rest[ 24..35 ] != r#"","record":"# {
+        return None;
+    }
+    Some( ( seq, &rest[ 8..24 ], &rest[ 35..rest.len() - 1 ] ) )
+}
+
+fn split_footer( line: &str ) -> Option<(u64, &str)> {
+    let line = line.trim_right();
+    if !line.starts_with( r#"{"seq":"# ) || !line.ends_with( "\"}" ) {
+        return None;
+    }
+    let rest = &line[ 7..line.len() - 2 ];
+    let marker = match rest.find( r#","footer":""# ) { Some( marker ) => marker, None => return None };
+    match rest[ ..marker ].parse() {
+        Ok( seq ) => Some( ( seq, &rest[ marker + 11.. ] ) ),
+        Err( _ ) => None,
+    }
+}
+
+/// Reads a file written by SequencedWriter, checking every record. Stops at the first damaged
+/// one; everything before it is returned, so a replay can carry on from the last good record.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use market::recording;
+/// let verified = recording::verify( recording::open( Path::new( "quotes.jsonl" ) ).unwrap() ).unwrap();
+/// if let Some( damage ) = verified.damage {
+///   println!("damaged after record {}: {:?}", verified.last_good, damage );
+/// }
+/// for json in verified.records {
+///   let quote: market::Quote = market::decode::decode( &json ).unwrap();
+///   println!("{} / {}", quote.bid, quote.ask );
+/// }
+/// ```
+pub fn verify<R: BufRead>( input: R ) -> Result<Verified, StockfighterErr> {
+    let mut verified = Verified { records: vec![], last_good: 0, damage: Some( Damage::Truncated ) };
+    let mut rolling = FNV_OFFSET;
+    let mut input = input;
+    let mut number = 0;
+    loop {
+        number += 1;
+        // Raw bytes, so a torn multi-byte write or a truncated gzip stream marks the damage
+        // instead of throwing away the good records before it
+        let mut bytes = vec![];
+        match input.read_until( b'\n', &mut bytes ) {
+            Ok( 0 ) => break,
+            Ok( _ ) => {},
+            Err( _ ) => {
+                verified.damage = Some( Damage::Unreadable { line: number } );
+                return Ok( verified );
+            },
+        }
+        let line = match String::from_utf8( bytes ) {
+            Ok( line ) => line,
+            Err( _ ) => {
+                verified.damage = Some( Damage::Unreadable { line: number } );
+                return Ok( verified );
+            },
+        };
+        let line = line.trim_right_matches( |c| c == '\n' || c == '\r' );
+        if let Some( ( seq, sum, record ) ) = split_record( line ) {
+            if seq != verified.last_good + 1 {
+                verified.damage = Some( Damage::OutOfSequence { expected: verified.last_good + 1, found: seq } );
+                return Ok( verified );
+            }
+            if format!("{:016x}", fnv1a( FNV_OFFSET, record.as_bytes() ) ) != sum {
+                verified.damage = Some( Damage::BadChecksum { seq: seq } );
+                return Ok( verified );
+            }
+            rolling = fnv1a( rolling, sum.as_bytes() );
+            verified.records.push( record.to_owned() );
+            verified.last_good = seq;
+        } else if let Some( ( seq, sum ) ) = split_footer( line ) {
+            let intact = seq == verified.last_good && sum == format!("{:016x}", rolling );
+            verified.damage = if intact { None } else { Some( Damage::BadFooter ) };
+            return Ok( verified );
+        } else {
+            verified.damage = Some( Damage::Unreadable { line: number } );
+            return Ok( verified );
+        }
+    }
+    Ok( verified )
+}
+
+/// Feeds every event in a recording to `handler`, in order, and returns how many there were.
+/// Takes one JSON MarketEvent per line or a SequencedWriter file (footer skipped); checksums
+/// aren't checked here, so run a sequenced file through `verify` first if it might be damaged.
+///
+/// # Example
+/// ```
+/// use market::event::MarketEvent;
+/// use market::recording::{self, SequencedWriter};
+/// let path = std::env::temp_dir().join( "market-replay-doctest.jsonl" );
+/// let quote: market::Quote = market::decode::decode( market::fixtures::QUOTE ).unwrap();
+/// let mut out = SequencedWriter::new( recording::create( &path ).unwrap() );
+/// out.write_event( &MarketEvent::Quote( quote.clone() ) ).unwrap();
+/// out.write_event( &MarketEvent::trade_from( &quote ).unwrap() ).unwrap();
+/// out.finish().unwrap();
+///
+/// let mut kinds = vec![];
+/// let count = recording::replay( recording::open( &path ).unwrap(),
+///                                |event| kinds.push( event.kind().to_owned() ) ).unwrap();
+/// assert_eq!( count, 2 );
+/// assert_eq!( kinds, vec![ "quote", "trade" ] );
+/// ```
+pub fn replay<R: BufRead, F: FnMut( &MarketEvent )>( input: R, mut handler: F ) -> Result<u64, StockfighterErr> {
+    let mut count = 0;
+    for line in input.lines() {
+        let line = try!( line );
+        let line = line.trim();
+        if line.is_empty() || split_footer( line ).is_some() {
+            continue;
+        }
+        let json = match split_record( line ) {
+            Some( ( _, _, record ) ) => record,
+            None => line,
+        };
+        let event: MarketEvent = try!( decode::decode( json ) );
+        handler( &event );
+        count += 1;
+    }
+    Ok( count )
+}
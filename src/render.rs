@@ -0,0 +1,63 @@
+// Terminal rendering helpers. Nothing in here talks to the network -- it only formats
+// structs that have already been filled in by the API calls in lib.rs
+
+use price::format_price;
+use {Bid, OrderBook, OrderResponse};
+
+// Each side of the depth view gets this many columns, leaving room for the " | " divider
+// inside an 80 column terminal
+static SIDE_WIDTH: usize = 37;
+
+fn is_mine( level: &Bid, book: &OrderBook, my_orders: &[OrderResponse] ) -> bool {
+    let direction = if level.is_buy { "buy" } else { "sell" };
+    my_orders.iter().any( |order| {
+        order.open &&
+        order.price == level.price &&
+        order.direction == direction &&
+        order.venue == book.venue &&
+        order.symbol == book.symbol
+    })
+}
+
+impl OrderBook {
+    /// Renders the book as a side-by-side depth view (bids on the left, asks on the right)
+    /// that fits in an 80 column terminal.
+    ///
+    /// Any price level that one of `my_orders` is resting on is marked with a `*`, so you can
+    /// see at a glance where your own orders sit in the book. Only the first `depth` levels of
+    /// each side are shown.
+    ///
+    /// # Example
+    /// ```
+    /// let mut book = market::OrderBook::new( "TESTEX".to_owned(), "FOOBAR".to_owned() );
+    /// book.refresh().unwrap();
+    /// println!("{}", book.render( &[], 10 ) );
+    /// ```
+    pub fn render( &self, my_orders: &[OrderResponse], depth: usize ) -> String {
+        let mut out = String::new();
+        out.push_str( &format!("{} on {} at {}\n", self.symbol, self.venue, self.ts) );
+        out.push_str( &format!("{:^width$} | {:^width$}\n", "BIDS", "ASKS", width = SIDE_WIDTH) );
+        out.push_str( &format!("{:>1} {:>14} {:>20} | {:<20} {:<14} {:>1}\n",
+                               "", "qty", "price", "price", "qty", "") );
+
+        let rows = depth.min( self.bids.len().max( self.asks.len() ) );
+        for i in 0..rows {
+            let bid = match self.bids.get( i ) {
+                Some( level ) => {
+                    let mark = if is_mine( level, self, my_orders ) { "*" } else { "" };
+                    format!("{:>1} {:>14} {:>20}", mark, level.qty, format_price( level.price ))
+                },
+                None => "".to_owned(),
+            };
+            let ask = match self.asks.get( i ) {
+                Some( level ) => {
+                    let mark = if is_mine( level, self, my_orders ) { "*" } else { "" };
+                    format!("{:<20} {:<14} {:>1}", format_price( level.price ), level.qty, mark)
+                },
+                None => "".to_owned(),
+            };
+            out.push_str( &format!("{:<width$} | {}\n", bid, ask, width = SIDE_WIDTH) );
+        }
+        out
+    }
+}
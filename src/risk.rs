@@ -0,0 +1,192 @@
+// Exposure summaries.
+//
+// On the later levels the question is less "am I making money" than "how much could I lose":
+// how big the book is in dollars, how much of it sits in one name, and how fast it's turning
+// over. RiskSummary answers that from a blotter, a set of quotes and the same Marking used for
+// P&L. It's cheap to build, so take one every few seconds from a Scheduler timer and log it.
+
+use Quote;
+use blotter::Blotter;
+use mark::Marking;
+
+// Days from 1970-01-01 to the given civil date (proleptic Gregorian)
+fn days_from_civil( year: i64, month: i64, day: i64 ) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = ( 153 * ( ( month + 9 ) % 12 ) + 2 ) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn digits( text: &str ) -> Option<i64> {
+    if text.is_empty() || !text.bytes().all( |b| b >= b'0' && b <= b'9' ) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+// An ISO-8601 timestamp as the API writes them ("2015-07-05T22:16:18+00:00",
+// "2015-12-04T09:02:16.680986205Z") as (seconds since the epoch, nanoseconds), so fractions and
+// offsets compare properly. No offset means UTC. None if it doesn't parse.
+fn timestamp( ts: &str ) -> Option<(i64, u32)> {
+    let ts = ts.trim();
+    if ts.len() < 19 || !ts.is_char_boundary( 19 ) {
+        return None;
+    }
+    let ( date_time, mut rest ) = ts.split_at( 19 );
+    let b = date_time.as_bytes();
+    if b[4] != b'-' || b[7] != b'-' || ( b[10] != b'T' && b[10] != b' ' ) || b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let field = |from: usize, to: usize| digits( &date_time[ from..to ] );
+    let ( year, month, day, hour, minute, second ) =
+        match ( field( 0, 4 ), field( 5, 7 ), field( 8, 10 ), field( 11, 13 ), field( 14, 16 ), field( 17, 19 ) ) {
+            ( Some( year ), Some( month ), Some( day ), Some( hour ), Some( minute ), Some( second ) ) => {
+                ( year, month, day, hour, minute, second )
+            },
+            _ => return None,
+        };
+    let mut nanos = 0u32;
+    if rest.starts_with( '.' ) {
+        let end = rest[ 1.. ].find( |c: char| !c.is_digit( 10 ) ).map_or( rest.len(), |end| end + 1 );
+        let fraction = &rest[ 1..end ];
+        if fraction.is_empty() {
+            return None;
+        }
+        // Past nanoseconds the digits don't matter
+        for ( place, digit ) in fraction.bytes().chain( ::std::iter::repeat( b'0' ) ).take( 9 ).enumerate() {
+            nanos += ( digit - b'0' ) as u32 * 10u32.pow( 8 - place as u32 );
+        }
+        rest = &rest[ end.. ];
+    }
+    let offset = match rest {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes()[ 0 ] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let zone = rest[ 1.. ].replace( ":", "" );
+            if zone.len() != 4 || !zone.is_char_boundary( 2 ) {
+                return None;
+            }
+            match ( digits( &zone[ 0..2 ] ), digits( &zone[ 2..4 ] ) ) {
+                ( Some( hours ), Some( minutes ) ) => sign * ( hours * 3600 + minutes * 60 ),
+                _ => return None,
+            }
+        },
+    };
+    let days = days_from_civil( year, month, day );
+    Some( ( days * 86400 + hour * 3600 + minute * 60 + second - offset, nanos ) )
+}
+
+// `fill_ts` is at or after `since`: by time where both parse, as plain strings otherwise
+fn at_or_after( fill_ts: &str, since: &str ) -> bool {
+    match ( timestamp( fill_ts ), timestamp( since ) ) {
+        ( Some( fill ), Some( since ) ) => fill >= since,
+        _ => fill_ts >= since,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskSummary {
+    /// Sum of |position * mark| across symbols, in cents
+    pub gross: i64,
+    /// Sum of position * mark across symbols, in cents -- longs positive, shorts negative
+    pub net: i64,
+    /// The symbol with the largest absolute exposure, and that exposure in cents
+    pub largest: Option<(String, i64)>,
+    /// Largest absolute exposure as a fraction of gross, 0.0 when flat
+    pub concentration: f64,
+    /// Traded value (|qty * price| over every fill) in the window, in cents
+    pub turnover: i64,
+    /// Symbols with a position that couldn't be marked, and so aren't in the exposure figures
+    pub unmarked: Vec<String>,
+}
+
+impl RiskSummary {
+    /// Summarizes `tag`'s book. Turnover counts fills whose timestamp is at or after `since`
+    /// (an ISO-8601 string, with or without fractional seconds, in any offset), or every fill
+    /// with None. Timestamps are compared as times, not as strings.
+    ///
+    /// # Example
+    /// ```
+    /// use market::blotter::Blotter;
+    /// use market::mark::{Marking, MarkSource};
+    /// use market::risk::RiskSummary;
+    /// let client = market::StockfighterClient::new();
+    /// let blotter = Blotter::new();
+    /// let quotes = vec![ client.quote( "TESTEX", "FOOBAR" ).unwrap() ];
+    /// let risk = RiskSummary::from_blotter( &blotter, "mm", &quotes, &Marking::new( MarkSource::Mid ),
+    ///                                       Some( "2016-01-01T00:00:00Z" ) );
+    /// println!("gross ${:.2}, {:.0}% in {:?}", risk.gross as f64 / 100.0,
+    ///          risk.concentration * 100.0, risk.largest );
+    /// ```
+    ///
+    /// ```
+    /// use market::blotter::Blotter;
+    /// use market::mark::{Marking, MarkSource};
+    /// use market::risk::RiskSummary;
+    /// // Two fills, both at 2015-07-05T22:16:18+00:00
+    /// let mut blotter = Blotter::new();
+    /// blotter.record( "mm", market::decode::decode( market::fixtures::ORDER ).unwrap() );
+    /// let marking = Marking::new( MarkSource::Mid );
+    /// let both = 50 * 5050 + 30 * 5100;
+    /// for &( since, turnover ) in &[ ( "2015-07-05T22:16:17.5Z", both ),
+    ///                                ( "2015-07-05T22:16:18Z", both ),
+    ///                                ( "2015-07-05T22:16:18.5Z", 0 ),
+    ///                                ( "2015-07-05T23:16:17+01:00", both ) ] {
+    ///   let risk = RiskSummary::from_blotter( &blotter, "mm", &[], &marking, Some( since ) );
+    ///   assert_eq!( risk.turnover, turnover );
+    /// }
+    /// ```
+    pub fn from_blotter( blotter: &Blotter, tag: &str, quotes: &[Quote], marking: &Marking,
+                         since: Option<&str> ) -> RiskSummary {
+        let mut gross = 0;
+        let mut net = 0;
+        let mut largest: Option<(String, i64)> = None;
+        let mut unmarked = vec![];
+        for line in marking.value( blotter, tag, quotes ) {
+            if line.position == 0 {
+                continue;
+            }
+            let mark = match line.mark {
+                Some( mark ) => mark,
+                None => {
+                    unmarked.push( line.symbol );
+                    continue;
+                },
+            };
+            let exposure = line.position as i64 * mark as i64;
+            gross += exposure.abs();
+            net += exposure;
+            let bigger = match largest {
+                Some( ( _, biggest ) ) => exposure.abs() > biggest.abs(),
+                None => true,
+            };
+            if bigger {
+                largest = Some( ( line.symbol, exposure ) );
+            }
+        }
+        let concentration = match largest {
+            Some( ( _, biggest ) ) if gross > 0 => biggest.abs() as f64 / gross as f64,
+            _ => 0.0,
+        };
+        let turnover = blotter.by_tag( tag ).iter().map( |entry| {
+            entry.order.fills.iter()
+                 .filter( |fill| since.map( |since| at_or_after( &fill.ts, since ) ).unwrap_or( true ) )
+                 .map( |fill| fill.qty.get() as i64 * fill.price as i64 )
+                 .sum::<i64>()
+        }).sum();
+        RiskSummary {
+            gross: gross,
+            net: net,
+            largest: largest,
+            concentration: concentration,
+            turnover: turnover,
+            unmarked: unmarked,
+        }
+    }
+}
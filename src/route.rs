@@ -0,0 +1,163 @@
+// Every endpoint the library talks to, and the one place their URLs get built.
+//
+// Venue and symbol strings come from users (and, eventually, from config files), so each path
+// segment is percent-encoded rather than pasted into a format! string. An empty segment is
+// rejected outright -- "/venues//stocks" is never what anyone meant.
+
+use std::fmt::Write;
+
+use {StockfighterErr, STOCKFIGHTER_API_URL};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route<'a> {
+    Heartbeat,
+    VenueHeartbeat { venue: &'a str },
+    VenueStocks { venue: &'a str },
+    OrderBook { venue: &'a str, stock: &'a str },
+    Quote { venue: &'a str, stock: &'a str },
+    Orders { venue: &'a str, stock: &'a str },
+    Order { venue: &'a str, stock: &'a str, id: i32 },
+    AccountOrders { venue: &'a str, account: &'a str },
+}
+
+// RFC 3986 unreserved characters pass through untouched, everything else is %XX encoded
+fn push_segment( out: &mut String, segment: &str ) -> Result<(), StockfighterErr> {
+    if segment.is_empty() {
+        return Err( StockfighterErr::BadRoute( "empty path segment".to_owned() ) );
+    }
+    // Dots are unreserved, but "." and ".." get normalized away by clients and proxies
+    if segment == "." || segment == ".." {
+        return Err( StockfighterErr::BadRoute( format!("dot path segment {:?}", segment) ) );
+    }
+    out.push( '/' );
+    for byte in segment.bytes() {
+        match byte {
+            b'A'...b'Z' | b'a'...b'z' | b'0'...b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push( byte as char );
+            },
+            _ => {
+                // Writing into a String can't fail
+                let _ = write!( out, "%{:02X}", byte );
+            },
+        }
+    }
+    Ok( () )
+}
+
+impl<'a> Route<'a> {
+    /// A short name for the endpoint, without any venue/stock in it.
+    pub fn name( &self ) -> &'static str {
+        match *self {
+            Route::Heartbeat => "heartbeat",
+            Route::VenueHeartbeat { .. } => "venue_heartbeat",
+            Route::VenueStocks { .. } => "venue_stocks",
+            Route::OrderBook { .. } => "order_book",
+            Route::Quote { .. } => "quote",
+            Route::Orders { .. } => "orders",
+            Route::Order { .. } => "order",
+            Route::AccountOrders { .. } => "account_orders",
+        }
+    }
+
+    /// Builds the full URL for this route.
+    ///
+    /// # Example
+    /// ```
+    /// use market::route::Route;
+    /// let url = Route::Quote { venue: "TESTEX", stock: "FOOBAR" }.url().unwrap();
+    /// assert_eq!( url, "https://api.stockfighter.io/ob/api/venues/TESTEX/stocks/FOOBAR/quote" );
+    ///
+    /// assert!( Route::Quote { venue: "", stock: "FOOBAR" }.url().is_err() );
+    /// ```
+    ///
+    /// Whatever the venue and stock strings hold, the result is either a URL with every
+    /// segment encoded or `BadRoute` -- never a panic, never an extra path segment, and never a
+    /// `.` or `..` segment for something along the way to resolve.
+    ///
+    /// ```
+    /// use market::StockfighterErr;
+    /// use market::route::Route;
+    /// let hostile = [ "", "/", ".", "..", "...", "a/b", "?x=1", "#frag", " ", "%", "%2F", "\u{0}", "ünï",
+    ///                 "\u{1F4C8}", "\r\n", "TESTEX/../../admin" ];
+    /// for venue in hostile.iter() {
+    ///     for stock in hostile.iter() {
+    ///         match Route::Order { venue: *venue, stock: *stock, id: -1 }.url() {
+    ///             Ok( url ) => {
+    ///                 assert!( url.bytes().all( |b| b < 0x80 ) );
+    ///                 assert_eq!( url.matches( '/' ).count(), 10 );
+    ///                 assert!( !url.contains( '?' ) && !url.contains( '#' ) );
+    ///                 assert!( url.split( '/' ).all( |segment| segment != "." && segment != ".." ) );
+    ///             },
+    ///             Err( StockfighterErr::BadRoute( _ ) ) => {
+    ///                 let bad = |segment: &str| segment.is_empty() || segment == "." || segment == "..";
+    ///                 assert!( bad( *venue ) || bad( *stock ) );
+    ///             },
+    ///             Err( err ) => panic!( "unexpected error: {}", err ),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn url( &self ) -> Result<String, StockfighterErr> {
+        let mut url = String::new();
+        try!( self.write_url( &mut url ) );
+        Ok( url )
+    }
+
+    /// Same as `url`, but writes into an existing buffer (which is cleared first) so callers
+    /// in a polling loop can reuse it.
+    pub fn write_url( &self, out: &mut String ) -> Result<(), StockfighterErr> {
+        out.clear();
+        out.push_str( STOCKFIGHTER_API_URL );
+        match *self {
+            Route::Heartbeat => {
+                out.push_str( "/heartbeat" );
+            },
+            Route::VenueHeartbeat { venue } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/heartbeat" );
+            },
+            Route::VenueStocks { venue } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/stocks" );
+            },
+            Route::OrderBook { venue, stock } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/stocks" );
+                try!( push_segment( out, stock ) );
+            },
+            Route::Quote { venue, stock } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/stocks" );
+                try!( push_segment( out, stock ) );
+                out.push_str( "/quote" );
+            },
+            Route::Orders { venue, stock } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/stocks" );
+                try!( push_segment( out, stock ) );
+                out.push_str( "/orders" );
+            },
+            Route::Order { venue, stock, id } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/stocks" );
+                try!( push_segment( out, stock ) );
+                out.push_str( "/orders" );
+                try!( push_segment( out, &id.to_string() ) );
+            },
+            Route::AccountOrders { venue, account } => {
+                out.push_str( "/venues" );
+                try!( push_segment( out, venue ) );
+                out.push_str( "/accounts" );
+                try!( push_segment( out, account ) );
+                out.push_str( "/orders" );
+            },
+        }
+        Ok( () )
+    }
+}
@@ -0,0 +1,103 @@
+// Smart order routing across venues.
+//
+// Given a ConsolidatedBook, work out how to split one order between venues to get the best
+// expected price: walk the opposite side of the consolidated ladder best price first, taking
+// what each venue shows at each level until the quantity is covered. Each venue then gets a
+// single limit order priced at the worst level we expect to take there, which sweeps its
+// better levels on the way.
+
+use std::collections::BTreeMap;
+
+use consolidated::ConsolidatedBook;
+use {Order, OrderResponse, Qty, StockfighterErr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildOrder {
+    pub venue: String,
+    pub price: i32,
+    pub qty: Qty,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutingPlan {
+    pub direction: String,
+    pub children: Vec<ChildOrder>,
+    /// Quantity the visible books (within the limit) couldn't cover
+    pub unrouted: Qty,
+    /// What the routed quantity should cost (or raise), in cents, if the books hold still
+    pub expected_value: i64,
+}
+
+/// Plans a `direction` ("buy" or "sell") order for `qty` shares. With a `limit`, levels
+/// priced through it are left alone.
+///
+/// # Example
+/// ```
+/// use market::consolidated::ConsolidatedBook;
+/// let mut books = vec![];
+/// for venue in &[ "TESTEX", "OBEX" ] {
+///   let mut book = market::OrderBook::new( venue.to_string(), "FOOBAR".to_owned() );
+///   book.refresh().unwrap();
+///   books.push( book );
+/// }
+/// let plan = market::router::plan( &ConsolidatedBook::from_books( &books ), "buy", market::Qty( 500 ), Some( 5100 ) );
+/// for ( child, result ) in market::router::execute( &plan, "EXB123456", "FOOBAR" ) {
+///   println!("{} x {} @ {} -> {:?}", child.venue, child.qty, child.price, result.is_ok() );
+/// }
+/// ```
+pub fn plan( book: &ConsolidatedBook, direction: &str, qty: Qty, limit: Option<i32> ) -> RoutingPlan {
+    let buying = direction == "buy";
+    let levels = if buying { &book.asks } else { &book.bids };
+
+    // venue -> (worst price, qty), BTreeMap so children come out in a stable order
+    let mut per_venue: BTreeMap<String, (i32, Qty)> = BTreeMap::new();
+    let mut remaining = qty;
+    let mut value: i64 = 0;
+    'levels: for level in levels {
+        if let Some( limit ) = limit {
+            if ( buying && level.price > limit ) || ( !buying && level.price < limit ) {
+                break;
+            }
+        }
+        for venue in &level.venues {
+            if remaining.is_zero() {
+                break 'levels;
+            }
+            let take = venue.qty.min( remaining );
+            if take.is_zero() {
+                continue;
+            }
+            remaining = remaining.saturating_sub( take );
+            value += take.get() as i64 * level.price as i64;
+            let entry = per_venue.entry( venue.venue.clone() ).or_insert( ( level.price, Qty( 0 ) ) );
+            // Levels are walked best first, so the latest price is always the worst one
+            entry.0 = level.price;
+            entry.1 = entry.1.saturating_add( take );
+        }
+    }
+
+    RoutingPlan {
+        direction: direction.to_owned(),
+        children: per_venue.into_iter()
+                           .map( |( venue, ( price, qty ) )| ChildOrder { venue: venue, price: price, qty: qty } )
+                           .collect(),
+        unrouted: remaining,
+        expected_value: value,
+    }
+}
+
+/// Sends every child order in `plan` as a limit order, returning each child with its result.
+/// One venue failing doesn't stop the others from being sent.
+pub fn execute( plan: &RoutingPlan, account: &str, stock: &str )
+                -> Vec<(ChildOrder, Result<OrderResponse, StockfighterErr>)> {
+    plan.children.iter().map( |child| {
+        let order = Order::new( account.to_owned(),
+                                child.venue.clone(),
+                                stock.to_owned(),
+                                child.price,
+                                child.qty,
+                                plan.direction.clone(),
+                                "limit".to_owned() );
+        ( child.clone(), order.process_order() )
+    }).collect()
+}
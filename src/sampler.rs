@@ -0,0 +1,132 @@
+// Order book sampling that speeds up when it matters.
+//
+// Polling the book at a fixed rate is either wasteful (a quiet market, nothing resting) or too
+// slow (the mid is jumping and we have orders in the book). BookSampler starts from a base
+// interval, halves it whenever the mid moves by at least `move_threshold` cents or the number
+// of our resting orders goes up, and lets it drift back out by half again each quiet sample,
+// always staying between `fastest` and `slowest`. The fastest setting is the one to size
+// against the rate limit.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use {OrderBook, StockfighterErr};
+use cancel::CancelToken;
+use clock::{duration_millis, Clock, SystemClock};
+use fair::FairValue;
+
+pub struct BookSampler {
+    interval: u64,
+    fastest: u64,
+    slowest: u64,
+    move_threshold: f64,
+    last_mid: Option<f64>,
+    last_open: usize,
+    clock: Arc<Clock>,
+}
+
+impl BookSampler {
+    /// Samples every `base` to begin with, never faster than a tenth of that and never slower
+    /// than four times it, treating a one cent move in the mid as activity.
+    pub fn new( base: Duration ) -> BookSampler {
+        let base = duration_millis( base ).max( 1 );
+        BookSampler {
+            interval: base,
+            fastest: ( base / 10 ).max( 1 ),
+            slowest: base.saturating_mul( 4 ),
+            move_threshold: 1.0,
+            last_mid: None,
+            last_open: 0,
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    pub fn fastest( mut self, fastest: Duration ) -> BookSampler {
+        self.fastest = duration_millis( fastest ).max( 1 );
+        self
+    }
+
+    pub fn slowest( mut self, slowest: Duration ) -> BookSampler {
+        self.slowest = duration_millis( slowest ).max( 1 );
+        self
+    }
+
+    /// How far the mid has to move between samples, in cents, to count as activity.
+    pub fn move_threshold( mut self, cents: f64 ) -> BookSampler {
+        self.move_threshold = cents;
+        self
+    }
+
+    /// Sleeps through `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> BookSampler {
+        self.clock = clock;
+        self
+    }
+
+    /// The wait before the next sample, as things stand.
+    pub fn interval( &self ) -> Duration {
+        Duration::from_millis( self.interval )
+    }
+
+    /// Folds in a fresh `book` and the number of our orders currently resting, and returns
+    /// the wait before the next sample. More resting orders than last time counts as
+    /// activity; a steady number -- a market maker's two quotes, say -- doesn't.
+    pub fn observe( &mut self, book: &OrderBook, open_orders: usize ) -> Duration {
+        let mid = book.fair_value( FairValue::Mid );
+        let moved = match ( self.last_mid, mid ) {
+            ( Some( before ), Some( now ) ) => ( now - before ).abs() >= self.move_threshold,
+            _ => false,
+        };
+        if mid.is_some() {
+            self.last_mid = mid;
+        }
+        let more_orders = open_orders > self.last_open;
+        self.last_open = open_orders;
+        let ceiling = self.slowest.max( self.fastest );
+        if moved || more_orders {
+            self.interval = ( self.interval / 2 ).max( self.fastest ).min( ceiling );
+        } else {
+            self.interval = self.interval.saturating_add( self.interval / 2 ).max( self.fastest ).min( ceiling );
+        }
+        self.interval()
+    }
+
+    /// Fetches the book, hands it to `on_book` and waits, until `token` is cancelled (which
+    /// returns Err(Cancelled)) or `fetch` fails.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use market::cancel::CancelToken;
+    /// use market::sampler::BookSampler;
+    /// let client = market::StockfighterClient::new();
+    /// let token = CancelToken::new();
+    /// let stop = token.clone();
+    /// let mut samples = 0;
+    /// BookSampler::new( Duration::from_millis( 500 ) )
+    ///   .fastest( Duration::from_millis( 100 ) )
+    ///   .sample( || client.order_book( "TESTEX", "FOOBAR" ),
+    ///            || 0,
+    ///            |book| {
+    ///              samples += 1;
+    ///              println!("{}", book.render( &[], 5 ) );
+    ///              if samples == 10 { stop.cancel(); }
+    ///            },
+    ///            &token )
+    ///   .ok();
+    /// ```
+    pub fn sample<F, N, B>( &mut self, mut fetch: F, mut open_orders: N, mut on_book: B,
+                            token: &CancelToken ) -> Result<(), StockfighterErr>
+        where F: FnMut() -> Result<OrderBook, StockfighterErr>,
+              N: FnMut() -> usize,
+              B: FnMut( &OrderBook )
+    {
+        loop {
+            try!( token.check() );
+            let book = try!( fetch() );
+            on_book( &book );
+            let wait = self.observe( &book, open_orders() );
+            try!( token.sleep( &*self.clock, wait ) );
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Venue-wide scanner.
+//
+// On the multi-symbol levels it isn't obvious where to focus. A Scanner quotes every symbol a
+// venue lists, keeps a short history of each, and ranks the symbols by one criterion. Call
+// `scan` periodically (a Scheduler timer works well); the change callback fires whenever the
+// ranking order differs from the previous scan.
+
+use std::collections::{HashMap, VecDeque};
+
+use {Qty, Quote, StockfighterClient, StockfighterErr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    /// Widest current spread first
+    Spread,
+    /// Most shares traded over the window first
+    Volume,
+    /// Largest standard deviation of the last price over the window first
+    Volatility,
+    /// Largest absolute move in the last price over the window first
+    PriceChange,
+}
+
+#[derive(Debug)]
+pub struct ScanRow {
+    pub symbol: String,
+    pub score: f64,
+    pub quote: Quote,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    last: i32,
+    // Shares traded since the previous sample (0 if the last trade hasn't changed)
+    traded: Qty,
+}
+
+pub struct Scanner {
+    venue: String,
+    criterion: Criterion,
+    window: usize,
+    client: StockfighterClient,
+    symbols: Vec<String>,
+    history: HashMap<String, VecDeque<Sample>>,
+    last_trade: HashMap<String, String>,
+    ranking: Vec<String>,
+    on_change: Option<Box<FnMut( &[ScanRow] )>>,
+}
+
+impl Scanner {
+    /// Scans `venue`, ranking by `criterion` over the last 20 scans.
+    ///
+    /// # Example
+    /// ```
+    /// use market::scanner::{Scanner, Criterion};
+    /// let mut scanner = Scanner::new( "TESTEX".to_owned(), Criterion::Spread )
+    ///                       .on_change( |rows| println!("new leader: {}", rows[0].symbol ) );
+    /// for _ in 0..10 {
+    ///   for row in scanner.scan().unwrap() {
+    ///     println!("{:>8} {:>10.2}", row.symbol, row.score );
+    ///   }
+    /// }
+    /// ```
+    pub fn new( venue: String, criterion: Criterion ) -> Scanner {
+        Scanner {
+            venue: venue,
+            criterion: criterion,
+            window: 20,
+            client: StockfighterClient::new(),
+            symbols: vec![],
+            history: HashMap::new(),
+            last_trade: HashMap::new(),
+            ranking: vec![],
+            on_change: None,
+        }
+    }
+
+    /// How many scans of history Volume / Volatility / PriceChange look back over.
+    pub fn window( mut self, scans: usize ) -> Scanner {
+        self.window = scans.max( 1 );
+        self
+    }
+
+    pub fn on_change<F: FnMut( &[ScanRow] ) + 'static>( mut self, callback: F ) -> Scanner {
+        self.on_change = Some( Box::new( callback ) );
+        self
+    }
+
+    fn score( &self, symbol: &str, quote: &Quote ) -> f64 {
+        let history = match self.history.get( symbol ) {
+            Some( history ) => history,
+            None => return 0.0,
+        };
+        match self.criterion {
+            Criterion::Spread => {
+                if quote.bid > 0 && quote.ask > 0 { ( quote.ask - quote.bid ) as f64 } else { 0.0 }
+            },
+            Criterion::Volume => {
+                history.iter().map( |sample| sample.traded.get() as f64 ).sum()
+            },
+            Criterion::Volatility => {
+                let n = history.len() as f64;
+                let mean = history.iter().map( |sample| sample.last as f64 ).sum::<f64>() / n;
+                let variance = history.iter()
+                                      .map( |sample| ( sample.last as f64 - mean ).powi( 2 ) )
+                                      .sum::<f64>() / n;
+                variance.sqrt()
+            },
+            Criterion::PriceChange => {
+                match ( history.front(), history.back() ) {
+                    ( Some( first ), Some( last ) ) => ( last.last - first.last ).abs() as f64,
+                    _ => 0.0,
+                }
+            },
+        }
+    }
+
+    /// Quotes every symbol on the venue and returns them ranked, best first. The venue's stock
+    /// listing is fetched on the first scan only. A symbol that fails to quote is left out of
+    /// this scan rather than failing the whole thing.
+    pub fn scan( &mut self ) -> Result<Vec<ScanRow>, StockfighterErr> {
+        if self.symbols.is_empty() {
+            let stocks = try!( self.client.stocks( &self.venue ) );
+            self.symbols = stocks.into_iter().map( |stock| stock.symbol ).collect();
+        }
+
+        let mut quotes = vec![];
+        for symbol in &self.symbols {
+            if let Ok( quote ) = self.client.quote( &self.venue, symbol ) {
+                quotes.push( quote );
+            }
+        }
+
+        for quote in &quotes {
+            let traded = match self.last_trade.insert( quote.symbol.clone(), quote.last_trade.clone() ) {
+                Some( ref previous ) if *previous == quote.last_trade => Qty( 0 ),
+                _ => quote.last_size,
+            };
+            let history = self.history.entry( quote.symbol.clone() ).or_insert_with( VecDeque::new );
+            history.push_back( Sample { last: quote.last, traded: traded } );
+            while history.len() > self.window {
+                history.pop_front();
+            }
+        }
+
+        let mut rows: Vec<ScanRow> = quotes.into_iter().map( |quote| {
+            ScanRow {
+                symbol: quote.symbol.clone(),
+                score: self.score( &quote.symbol, &quote ),
+                quote: quote,
+            }
+        }).collect();
+        rows.sort_by( |a, b| b.score.partial_cmp( &a.score ).unwrap_or( ::std::cmp::Ordering::Equal ) );
+
+        let ranking: Vec<String> = rows.iter().map( |row| row.symbol.clone() ).collect();
+        if ranking != self.ranking {
+            self.ranking = ranking;
+            if let Some( ref mut callback ) = self.on_change {
+                callback( &rows );
+            }
+        }
+        Ok( rows )
+    }
+}
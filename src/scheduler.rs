@@ -0,0 +1,124 @@
+// Timers for strategies.
+//
+// A strategy usually already has a loop that polls quotes. Rather than spinning up threads for
+// "requote every 500ms" or "flatten at 15:59", register them here and call `tick` from that
+// same loop -- the timers then fire in-line with the market data, one `on_tick( tag )` each.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clock::{Clock, SystemClock};
+
+#[derive(Debug, Clone)]
+struct Timer {
+    tag: String,
+    due: Instant,
+    every: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    timers: Vec<Timer>,
+    clock: Arc<Clock>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Scheduler {
+        Scheduler::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler::with_clock( Arc::new( SystemClock ) )
+    }
+
+    /// A scheduler that reads the time from `clock`.
+    pub fn with_clock( clock: Arc<Clock> ) -> Scheduler {
+        Scheduler { timers: vec![], clock: clock }
+    }
+
+    /// Fires `tag` every `interval`, starting one interval from now.
+    pub fn every( &mut self, tag: &str, interval: Duration ) {
+        self.timers.push( Timer {
+            tag: tag.to_owned(),
+            due: self.clock.now() + interval,
+            every: Some( interval ),
+        });
+    }
+
+    /// Fires `tag` once, at `when`.
+    pub fn at( &mut self, tag: &str, when: Instant ) {
+        self.timers.push( Timer {
+            tag: tag.to_owned(),
+            due: when,
+            every: None,
+        });
+    }
+
+    /// Fires `tag` once, `delay` from now.
+    pub fn after( &mut self, tag: &str, delay: Duration ) {
+        let when = self.clock.now() + delay;
+        self.at( tag, when );
+    }
+
+    /// Removes every timer registered under `tag`.
+    pub fn cancel( &mut self, tag: &str ) {
+        self.timers.retain( |timer| timer.tag != tag );
+    }
+
+    /// When the next timer is due, if any are registered. Handy as a wait timeout.
+    pub fn next_due( &self ) -> Option<Instant> {
+        self.timers.iter().map( |timer| timer.due ).min()
+    }
+
+    /// Tags of every timer due at `now`. One-shot timers are removed; interval timers are
+    /// rescheduled. An interval timer that has fallen more than a whole interval behind fires
+    /// once and skips ahead, rather than firing a burst to catch up.
+    pub fn due( &mut self, now: Instant ) -> Vec<String> {
+        let mut fired = vec![];
+        for timer in &mut self.timers {
+            if timer.due > now {
+                continue;
+            }
+            fired.push( timer.tag.clone() );
+            if let Some( every ) = timer.every {
+                timer.due = timer.due + every;
+                if timer.due <= now {
+                    timer.due = now + every;
+                }
+            }
+        }
+        self.timers.retain( |timer| timer.every.is_some() || timer.due > now );
+        fired
+    }
+
+    /// Calls `on_tick` with the tag of every timer due right now.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// let mut scheduler = market::scheduler::Scheduler::new();
+    /// scheduler.every( "requote", Duration::from_millis( 500 ) );
+    /// scheduler.after( "flatten", Duration::from_secs( 60 ) );
+    /// let client = market::StockfighterClient::new();
+    /// loop {
+    ///   let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    ///   let mut done = false;
+    ///   scheduler.tick( |tag| {
+    ///     match tag {
+    ///       "requote" => println!("requote around {}", quote.last ),
+    ///       "flatten" => done = true,
+    ///       _ => {},
+    ///     }
+    ///   });
+    ///   if done { break; }
+    /// }
+    /// ```
+    pub fn tick<F: FnMut( &str )>( &mut self, mut on_tick: F ) {
+        let now = self.clock.now();
+        for tag in self.due( now ) {
+            on_tick( &tag );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// Schema drift detection.
+//
+// serde quietly ignores JSON fields that a struct doesn't declare, which is what we want in
+// normal running -- but it also means that when the server starts sending something new we
+// never find out. With strict mode on, every response body is also compared against the list
+// of fields its struct knows about, and anything unexpected gets reported (once per endpoint).
+
+use serde_json;
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::io;
+
+use {OrderBook, OrderResponse, Quote, StockfighterAPI, StockfighterVenue, StockfighterVenueStocks};
+
+/// The JSON field names (as sent on the wire, after any serde renames) a struct understands.
+pub trait Schema {
+    fn fields() -> &'static [&'static str];
+}
+
+impl Schema for StockfighterAPI {
+    fn fields() -> &'static [&'static str] {
+        &[ "ok", "error" ]
+    }
+}
+
+impl Schema for StockfighterVenue {
+    fn fields() -> &'static [&'static str] {
+        &[ "ok", "error", "venue" ]
+    }
+}
+
+impl Schema for StockfighterVenueStocks {
+    fn fields() -> &'static [&'static str] {
+        &[ "ok", "symbols" ]
+    }
+}
+
+impl Schema for OrderResponse {
+    fn fields() -> &'static [&'static str] {
+        &[ "ok", "error", "symbol", "venue", "direction", "originalQty", "qty", "price",
+           "orderType", "id", "account", "ts", "fills", "totalFilled", "open" ]
+    }
+}
+
+impl Schema for OrderBook {
+    fn fields() -> &'static [&'static str] {
+        &[ "ok", "venue", "symbol", "bids", "asks", "ts" ]
+    }
+}
+
+impl Schema for Quote {
+    fn fields() -> &'static [&'static str] {
+        &[ "ok", "symbol", "venue", "bid", "ask", "bidSize", "askSize", "bidDepth", "askDepth",
+           "last", "lastSize", "lastTrade", "quoteTime" ]
+    }
+}
+
+/// Top level fields in `body` that `T` doesn't know about. A body that isn't a JSON object
+/// has no fields to compare, and comes back empty.
+///
+/// # Example
+/// ```
+/// use market::schema::unknown_fields;
+/// let body = r#"{"ok":true,"symbol":"FOOBAR","venue":"TESTEX","halted":false}"#;
+/// assert_eq!( unknown_fields::<market::Quote>( body ), vec![ "halted".to_owned() ] );
+/// ```
+pub fn unknown_fields<T: Schema>( body: &str ) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str( body ) {
+        Ok( value ) => value,
+        Err( _ ) => return vec![],
+    };
+    match value.as_object() {
+        Some( object ) => object.keys()
+                                .filter( |key| !T::fields().contains( &key.as_str() ) )
+                                .cloned()
+                                .collect(),
+        None => vec![],
+    }
+}
+
+/// Remembers which (endpoint, field) pairs have already been reported so a long running bot
+/// only hears about each new field once.
+#[derive(Debug, Default)]
+pub struct DriftLog {
+    seen: HashSet<(String, String)>,
+}
+
+impl DriftLog {
+    pub fn new() -> DriftLog {
+        DriftLog { seen: HashSet::new() }
+    }
+
+    /// Checks `body` against `T`, reporting newly seen unknown fields to stderr. Returns just
+    /// the fields that hadn't been reported for `endpoint` before.
+    pub fn check<T: Schema>( &mut self, endpoint: &str, body: &str ) -> Vec<String> {
+        let mut new_fields = vec![];
+        for field in unknown_fields::<T>( body ) {
+            if self.seen.insert( ( endpoint.to_owned(), field.clone() ) ) {
+                let _ = writeln!( io::stderr(), "schema drift: {} sent unknown field `{}`",
+                                  endpoint, field );
+                new_fields.push( field );
+            }
+        }
+        new_fields
+    }
+
+    /// Every (endpoint, field) pair seen so far.
+    pub fn seen( &self ) -> Vec<(String, String)> {
+        self.seen.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,129 @@
+// Recent history, in memory.
+//
+// Indicators, reports and the anomaly detectors all want the same things from the recent past:
+// "the last 30 seconds of quotes", "positions between these two times". TimeSeries keeps
+// timestamped values in arrival order in a ring buffer -- once it holds `capacity` values the
+// oldest are dropped -- and answers window queries over them. Timestamps are milliseconds since
+// the Unix epoch, the same as the journal's.
+
+use std::collections::VecDeque;
+use std::collections::vec_deque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clock::{duration_millis, Clock, SystemClock};
+
+pub struct TimeSeries<T> {
+    points: VecDeque<(u64, T)>,
+    capacity: usize,
+    clock: Arc<Clock>,
+}
+
+impl<T> TimeSeries<T> {
+    /// Keeps at most `capacity` values.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use market::clock::MockClock;
+    /// use market::series::TimeSeries;
+    /// let clock = MockClock::new();
+    /// let mut mids = TimeSeries::new( 1000 ).with_clock( Arc::new( clock.clone() ) );
+    /// mids.push( 4550 );
+    /// clock.advance( Duration::from_secs( 20 ) );
+    /// mids.push( 4560 );
+    /// clock.advance( Duration::from_secs( 20 ) );
+    /// mids.push( 4555 );
+    /// let recent: Vec<i32> = mids.last( Duration::from_secs( 30 ) ).map( |&( _, mid )| mid ).collect();
+    /// assert_eq!( recent, vec![4560, 4555] );
+    /// ```
+    pub fn new( capacity: usize ) -> TimeSeries<T> {
+        TimeSeries {
+            points: VecDeque::with_capacity( capacity.min( 4096 ) ),
+            capacity: capacity.max( 1 ),
+            clock: Arc::new( SystemClock ),
+        }
+    }
+
+    /// Stamps values and measures windows with `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> TimeSeries<T> {
+        self.clock = clock;
+        self
+    }
+
+    /// Adds `value`, stamped now.
+    pub fn push( &mut self, value: T ) {
+        let now = self.clock.millis();
+        self.push_at( now, value );
+    }
+
+    /// Adds `value` with timestamp `ts` -- for replays. Values are expected in time order;
+    /// one older than the newest is still kept, but window queries may skip it.
+    pub fn push_at( &mut self, ts: u64, value: T ) {
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back( ( ts, value ) );
+    }
+
+    pub fn len( &self ) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty( &self ) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The newest value and its timestamp.
+    pub fn latest( &self ) -> Option<&( u64, T )> {
+        self.points.back()
+    }
+
+    /// Everything held, oldest first.
+    pub fn iter( &self ) -> vec_deque::Iter<( u64, T )> {
+        self.points.iter()
+    }
+
+    /// Values stamped within `window` of now, oldest first.
+    pub fn last( &self, window: Duration ) -> vec_deque::Iter<( u64, T )> {
+        let now = self.clock.millis();
+        self.between( now.saturating_sub( duration_millis( window ) ), now )
+    }
+
+    /// Values stamped from `from` to `to` inclusive, oldest first.
+    pub fn between( &self, from: u64, to: u64 ) -> vec_deque::Iter<( u64, T )> {
+        let start = self.first_at_or_after( from );
+        let end = self.first_at_or_after( to.saturating_add( 1 ) ).max( start );
+        let mut iter = self.points.iter();
+        // VecDeque has no range iterator yet, so skip to the window by hand
+        for _ in 0..start {
+            iter.next();
+        }
+        for _ in end..self.points.len() {
+            iter.next_back();
+        }
+        iter
+    }
+
+    /// Drops everything stamped before `ts`.
+    pub fn evict_before( &mut self, ts: u64 ) {
+        while self.points.front().map( |&( at, _ )| at < ts ).unwrap_or( false ) {
+            self.points.pop_front();
+        }
+    }
+
+    // Binary search over the (time ordered) buffer
+    fn first_at_or_after( &self, ts: u64 ) -> usize {
+        let ( mut low, mut high ) = ( 0, self.points.len() );
+        while low < high {
+            let middle = ( low + high ) / 2;
+            if self.points[ middle ].0 < ts {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+}
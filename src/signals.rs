@@ -0,0 +1,126 @@
+// Trading signals computed incrementally from market data.
+//
+// Each signal is fed one observation at a time and can be read at any point, so it costs the
+// same whether a bot has been running for a minute or a day.
+
+use std::time::{Duration, Instant};
+
+use {Qty, Quote};
+
+fn seconds( duration: Duration ) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
+
+/// Signed trade volume with exponential decay -- a running measure of whether the tape has
+/// recently been dominated by buyers (positive) or sellers (negative).
+///
+/// Each trade is signed by where it printed relative to the quote (at or above the ask is a
+/// buy, at or below the bid a sell); trades inside the spread fall back to the tick rule
+/// (up from the last trade is a buy, down a sell, unchanged keeps the previous sign).
+#[derive(Debug, Clone)]
+pub struct TradeMomentum {
+    half_life: f64,
+    buy_volume: f64,
+    sell_volume: f64,
+    updated: Option<Instant>,
+    last_price: Option<i32>,
+    last_sign: i32,
+    last_trade: String,
+}
+
+impl TradeMomentum {
+    /// Volume loses half its weight every `half_life`.
+    pub fn new( half_life: Duration ) -> TradeMomentum {
+        TradeMomentum {
+            half_life: seconds( half_life ).max( 1e-9 ),
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+            updated: None,
+            last_price: None,
+            last_sign: 0,
+            last_trade: String::new(),
+        }
+    }
+
+    fn decay( &mut self, now: Instant ) {
+        if let Some( updated ) = self.updated {
+            if now > updated {
+                let factor = 0.5f64.powf( seconds( now - updated ) / self.half_life );
+                self.buy_volume *= factor;
+                self.sell_volume *= factor;
+            }
+        }
+        if self.updated.map_or( true, |updated| now > updated ) {
+            self.updated = Some( now );
+        }
+    }
+
+    /// Feeds a quote in. Only quotes reporting a trade we haven't seen yet (a new
+    /// `last_trade` timestamp) count.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::time::{Duration, Instant};
+    /// let mut momentum = market::signals::TradeMomentum::new( Duration::from_secs( 30 ) );
+    /// let client = market::StockfighterClient::new();
+    /// loop {
+    ///   let quote = client.quote( "TESTEX", "FOOBAR" ).unwrap();
+    ///   momentum.update( &quote, Instant::now() );
+    ///   println!("imbalance {:+.2}", momentum.imbalance( Instant::now() ) );
+    /// }
+    /// ```
+    pub fn update( &mut self, quote: &Quote, now: Instant ) {
+        if quote.last_trade.is_empty() || quote.last_trade == self.last_trade {
+            return;
+        }
+        self.last_trade = quote.last_trade.clone();
+        let sign = if quote.ask > 0 && quote.last >= quote.ask {
+            1
+        } else if quote.bid > 0 && quote.last <= quote.bid {
+            -1
+        } else {
+            self.tick_sign( quote.last )
+        };
+        self.record( quote.last, quote.last_size, sign, now );
+    }
+
+    /// Feeds in a single trade, signed by the tick rule alone.
+    pub fn trade( &mut self, price: i32, qty: Qty, now: Instant ) {
+        let sign = self.tick_sign( price );
+        self.record( price, qty, sign, now );
+    }
+
+    fn tick_sign( &self, price: i32 ) -> i32 {
+        match self.last_price {
+            Some( last ) if price > last => 1,
+            Some( last ) if price < last => -1,
+            _ => self.last_sign,
+        }
+    }
+
+    fn record( &mut self, price: i32, qty: Qty, sign: i32, now: Instant ) {
+        self.decay( now );
+        if sign > 0 {
+            self.buy_volume += qty.get() as f64;
+        } else if sign < 0 {
+            self.sell_volume += qty.get() as f64;
+        }
+        self.last_price = Some( price );
+        if sign != 0 {
+            self.last_sign = sign;
+        }
+    }
+
+    /// Decayed buy volume minus decayed sell volume, as of `now`.
+    pub fn value( &mut self, now: Instant ) -> f64 {
+        self.decay( now );
+        self.buy_volume - self.sell_volume
+    }
+
+    /// `value` scaled to -1.0 (all selling) .. 1.0 (all buying); 0.0 with no volume.
+    pub fn imbalance( &mut self, now: Instant ) -> f64 {
+        self.decay( now );
+        let total = self.buy_volume + self.sell_volume;
+        if total <= 0.0 { 0.0 } else { ( self.buy_volume - self.sell_volume ) / total }
+    }
+}
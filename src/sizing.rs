@@ -0,0 +1,81 @@
+// Sizing orders by value.
+//
+// "Buy $10,000 of FOOBAR" is how people (and most risk limits) think; the API wants a share
+// count. Dividing is easy, getting the rounding right every time is what keeps being
+// rewritten: round down and never spend more than asked, round up and never spend less, or
+// round to the nearest share. All amounts are integer cents, as everywhere else.
+
+use {Order, Qty, Quote, StockfighterErr};
+use price::format_price;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Never exceed the notional
+    Down,
+    /// Never fall short of the notional
+    Up,
+    /// Closest to the notional, halves rounding up
+    Nearest,
+}
+
+/// How many shares at `price` cents make up `notional` cents.
+///
+/// # Example
+/// ```
+/// use market::sizing::{shares, Rounding};
+/// // $10,000 at $33.00
+/// assert_eq!( shares( 1000000, 3300, Rounding::Down ).unwrap(), market::Qty( 303 ) );
+/// assert_eq!( shares( 1000000, 3300, Rounding::Up ).unwrap(), market::Qty( 304 ) );
+/// assert!( shares( 1000000, 0, Rounding::Down ).is_err() );
+/// ```
+pub fn shares( notional: i64, price: i32, rounding: Rounding ) -> Result<Qty, StockfighterErr> {
+    if price <= 0 {
+        return Err( StockfighterErr::BadPrice( format_price( price ) ) );
+    }
+    let notional = notional.max( 0 );
+    let price = price as i64;
+    let count = match rounding {
+        Rounding::Down => notional / price,
+        Rounding::Up => ( notional + price - 1 ) / price,
+        Rounding::Nearest => ( notional + price / 2 ) / price,
+    };
+    Ok( Qty( count.min( u32::max_value() as i64 ) as u32 ) )
+}
+
+/// The price a `notional` order would trade at right now: the ask to buy, the bid to sell.
+/// Errors if that side of the book is empty.
+pub fn touch_price( quote: &Quote, buy: bool ) -> Result<i32, StockfighterErr> {
+    let price = if buy { quote.ask } else { quote.bid };
+    if price <= 0 {
+        return Err( StockfighterErr::BadPrice( format!("no {} in {} quote", if buy { "ask" } else { "bid" },
+                                                         quote.symbol ) ) );
+    }
+    Ok( price )
+}
+
+impl Order {
+    /// A limit order for `notional` cents' worth of `stock` at `price`.
+    ///
+    /// # Example
+    /// ```
+    /// use market::sizing::Rounding;
+    /// let order = market::Order::for_notional( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                          "FOOBAR".to_owned(), 1000000, 3300,
+    ///                                          "buy".to_owned(), Rounding::Down ).unwrap();
+    /// assert_eq!( order.qty, market::Qty( 303 ) );
+    /// ```
+    pub fn for_notional( account: String, venue: String, stock: String, notional: i64, price: i32,
+                         direction: String, rounding: Rounding ) -> Result<Order, StockfighterErr> {
+        let qty = try!( shares( notional, price, rounding ) );
+        Ok( Order::new( account, venue, stock, price, qty, direction, "limit".to_owned() ) )
+    }
+
+    /// A limit order for `notional` cents' worth, priced at the touch in `quote` -- the ask
+    /// to buy, the bid to sell -- so it's marketable now.
+    pub fn for_notional_at_touch( account: String, quote: &Quote, notional: i64, direction: String,
+                                  rounding: Rounding ) -> Result<Order, StockfighterErr> {
+        let price = try!( touch_price( quote, direction == "buy" ) );
+        Order::for_notional( account, quote.venue.clone(), quote.symbol.clone(), notional, price,
+                             direction, rounding )
+    }
+}
@@ -0,0 +1,264 @@
+// Supervised background threads.
+//
+// A bot's background pieces -- a heartbeat watchdog, a quote poller, an order queue -- tend to
+// run on their own threads, and when one of those panics or bails out with an error it just
+// stops. Nothing notices until the strategy wonders why its data went stale. A Supervisor
+// runs each worker in a loop instead: when the worker panics or returns an error it is
+// restarted after an exponential backoff (with a little jitter, so workers that failed together
+// don't all retry together), and every start, failure and restart is reported on a channel.
+//
+// A worker that returns Ok( () ) is done and isn't restarted.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use StockfighterErr;
+use cancel::CancelToken;
+use chaos::Rng;
+use clock::{Clock, SystemClock};
+use gate::HealthGate;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerEvent {
+    /// The worker was started; `attempt` is 0 the first time and counts restarts after that
+    Started { name: String, attempt: usize },
+    /// The worker returned an error
+    Failed { name: String, reason: String },
+    /// The worker panicked
+    Panicked { name: String, message: String },
+    /// The worker will be started again after `after`
+    Restarting { name: String, after: Duration },
+    /// The worker failed more than `max_restarts` times in a row and was left stopped
+    GaveUp { name: String },
+    /// The worker returned Ok, or the supervisor was stopped
+    Finished { name: String },
+}
+
+/// How long to wait before each restart: `initial`, doubling up to `max`, then shortened by a
+/// random fraction of up to `jitter` (0.0 - 1.0).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub jitter: f64,
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff {
+            initial: Duration::from_millis( 100 ),
+            max: Duration::from_secs( 30 ),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl Backoff {
+    /// The wait before restart number `attempt` (1 for the first restart).
+    pub fn delay( &self, attempt: usize, rng: &mut Rng ) -> Duration {
+        let mut delay = self.initial;
+        for _ in 1..attempt {
+            delay = delay * 2;
+            if delay >= self.max {
+                break;
+            }
+        }
+        let delay = if delay > self.max { self.max } else { delay };
+        let nanos = delay.as_secs() as f64 * 1e9 + delay.subsec_nanos() as f64;
+        let jittered = ( nanos * ( 1.0 - self.jitter.max( 0.0 ).min( 1.0 ) * rng.next_f64() ) ) as u64;
+        Duration::new( jittered / 1_000_000_000, ( jittered % 1_000_000_000 ) as u32 )
+    }
+}
+
+pub struct Supervisor {
+    backoff: Backoff,
+    max_restarts: Option<usize>,
+    clock: Arc<Clock>,
+    seed: Option<u64>,
+    gate: Option<Arc<HealthGate>>,
+    stopping: CancelToken,
+    sender: Sender<WorkerEvent>,
+    events: Receiver<WorkerEvent>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+fn panic_message( payload: Box<Any + Send> ) -> String {
+    if let Some( message ) = payload.downcast_ref::<&str>() {
+        return ( *message ).to_owned();
+    }
+    if let Some( message ) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    "unknown panic".to_owned()
+}
+
+impl Supervisor {
+    /// A supervisor with the default backoff that restarts workers indefinitely.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use market::supervisor::Supervisor;
+    /// let mut supervisor = Supervisor::new().max_restarts( 10 );
+    /// let stopping = supervisor.stop_flag();
+    /// supervisor.spawn( "watchdog", move || {
+    ///   let client = market::StockfighterClient::new();
+    ///   while !stopping.load( std::sync::atomic::Ordering::SeqCst ) {
+    ///     try!( client.warm_up() );
+    ///     std::thread::sleep( Duration::from_secs( 5 ) );
+    ///   }
+    ///   Ok( () )
+    /// });
+    /// for event in supervisor.events().iter().take( 3 ) {
+    ///   println!("{:?}", event );
+    /// }
+    /// supervisor.stop();
+    /// ```
+    pub fn new() -> Supervisor {
+        let ( sender, events ) = mpsc::channel();
+        Supervisor {
+            backoff: Backoff::default(),
+            max_restarts: None,
+            clock: Arc::new( SystemClock ),
+            seed: None,
+            gate: None,
+            stopping: CancelToken::new(),
+            sender: sender,
+            events: events,
+            workers: vec![],
+        }
+    }
+
+    pub fn backoff( mut self, backoff: Backoff ) -> Supervisor {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Leaves a worker stopped once it has failed this many times in a row. A run that lasts
+    /// longer than the backoff's `max` counts as healthy and resets the count.
+    pub fn max_restarts( mut self, restarts: usize ) -> Supervisor {
+        self.max_restarts = Some( restarts );
+        self
+    }
+
+    /// Sleeps between restarts through `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> Supervisor {
+        self.clock = clock;
+        self
+    }
+
+    /// Seeds the backoff jitter, so restart timings are the same from run to run. Each worker
+    /// gets its own stream, derived from `seed` and the order workers were spawned in.
+    pub fn seed( mut self, seed: u64 ) -> Supervisor {
+        self.seed = Some( seed );
+        self
+    }
+
+    /// Holds restarts until `gate` lets them through, so workers that died because the server
+    /// went away come back when it does, not each on their own schedule.
+    pub fn gate( mut self, gate: Arc<HealthGate> ) -> Supervisor {
+        self.gate = Some( gate );
+        self
+    }
+
+    /// Every worker's starts, failures and restarts, in the order they happened.
+    pub fn events( &self ) -> &Receiver<WorkerEvent> {
+        &self.events
+    }
+
+    /// Stops (as `stop` does, minus the joining) when `token` is cancelled, so one token can
+    /// shut down the supervisor along with everything else it was handed to.
+    pub fn cancel_with( mut self, token: CancelToken ) -> Supervisor {
+        self.stopping = token;
+        self
+    }
+
+    /// Set when `stop` is called. Long-running workers should check it and return Ok.
+    pub fn stop_flag( &self ) -> Arc<AtomicBool> {
+        self.stopping.flag()
+    }
+
+    /// Cancelled when `stop` is called. Hand it to pollers and loops inside workers so they
+    /// return promptly instead of running out their intervals.
+    pub fn token( &self ) -> CancelToken {
+        self.stopping.clone()
+    }
+
+    /// Runs `worker` on its own thread under supervision. It's called again for every restart,
+    /// so it should set up whatever state it needs each time.
+    pub fn spawn<F>( &mut self, name: &str, worker: F )
+        where F: FnMut() -> Result<(), StockfighterErr> + Send + 'static
+    {
+        let name = name.to_owned();
+        let backoff = self.backoff;
+        let max_restarts = self.max_restarts;
+        let clock = self.clock.clone();
+        let gate = self.gate.clone();
+        let mut rng = match self.seed {
+            // Spread neighbouring worker indexes far apart so their streams don't start out alike
+            Some( seed ) => Rng::new( seed ^ ( self.workers.len() as u64 + 1 ).wrapping_mul( 0x9E3779B97F4A7C15 ) ),
+            None => Rng::from_time(),
+        };
+        let stopping = self.stopping.clone();
+        let sender = self.sender.clone();
+        let mut worker = worker;
+        let handle = thread::spawn( move || {
+            let mut failures = 0;
+            let mut attempt = 0;
+            loop {
+                // The receiver going away just means nobody is watching
+                let _ = sender.send( WorkerEvent::Started { name: name.clone(), attempt: attempt } );
+                let started = clock.now();
+                let outcome = panic::catch_unwind( AssertUnwindSafe( || worker() ) );
+                match outcome {
+                    Ok( Ok( () ) ) => break,
+                    Ok( Err( err ) ) => {
+                        let _ = sender.send( WorkerEvent::Failed { name: name.clone(), reason: err.to_string() } );
+                    },
+                    Err( payload ) => {
+                        let _ = sender.send( WorkerEvent::Panicked { name: name.clone(), message: panic_message( payload ) } );
+                    },
+                }
+                if stopping.is_cancelled() {
+                    break;
+                }
+                if clock.now() - started > backoff.max {
+                    failures = 0;
+                }
+                failures += 1;
+                if let Some( max ) = max_restarts {
+                    if failures > max {
+                        let _ = sender.send( WorkerEvent::GaveUp { name: name.clone() } );
+                        return;
+                    }
+                }
+                let after = backoff.delay( failures, &mut rng );
+                let _ = sender.send( WorkerEvent::Restarting { name: name.clone(), after: after } );
+                if stopping.sleep( &*clock, after ).is_err() {
+                    break;
+                }
+                if let Some( ref gate ) = gate {
+                    if gate.wait_or_cancel( &stopping ).is_err() {
+                        break;
+                    }
+                }
+                attempt += 1;
+            }
+            let _ = sender.send( WorkerEvent::Finished { name: name.clone() } );
+        });
+        self.workers.push( handle );
+    }
+
+    /// Stops restarting workers, raises the stop flag and waits for every worker to return.
+    pub fn stop( &mut self ) {
+        self.stopping.cancel();
+        for handle in self.workers.drain( .. ) {
+            let _ = handle.join();
+        }
+    }
+}
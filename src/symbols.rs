@@ -0,0 +1,140 @@
+// What we've learned about each symbol.
+//
+// A strategy tuned on a $20 stock with a 5 cent spread does something silly on a $800 stock
+// with a $3 spread. SymbolTable watches the quotes going by and keeps, per symbol, the sort of
+// thing a trader would eyeball before trading it -- price range, typical spread, typical trade
+// size -- so parameters can be scaled to the instrument rather than hard-coded.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use {Quote, Stock, StockfighterErr};
+use client::StockfighterClient;
+use clock::{Clock, SystemClock};
+
+#[derive(Debug, Clone)]
+pub struct SymbolInfo {
+    pub venue: String,
+    pub symbol: String,
+    /// The full name from the venue's listing, if it has been loaded
+    pub name: Option<String>,
+    /// When the first quote was seen; None for a symbol only known from the listing
+    pub first_seen: Option<SystemTime>,
+    /// Lowest and highest last trade price seen, in cents
+    pub low: Option<i32>,
+    pub high: Option<i32>,
+    /// Mean spread over quotes with both sides present, in cents
+    pub avg_spread: Option<f64>,
+    /// Mean trade size, in shares, counting each trade once however many quotes report it
+    pub typical_size: Option<f64>,
+    pub quotes: u64,
+    spreads: u64,
+    sizes: u64,
+    // The last trade already folded into typical_size
+    last_trade: String,
+}
+
+impl SymbolInfo {
+    fn new( venue: &str, symbol: &str ) -> SymbolInfo {
+        SymbolInfo {
+            venue: venue.to_owned(),
+            symbol: symbol.to_owned(),
+            name: None,
+            first_seen: None,
+            low: None,
+            high: None,
+            avg_spread: None,
+            typical_size: None,
+            quotes: 0,
+            spreads: 0,
+            sizes: 0,
+            last_trade: String::new(),
+        }
+    }
+
+    fn observe( &mut self, quote: &Quote, now: SystemTime ) {
+        if self.first_seen.is_none() {
+            self.first_seen = Some( now );
+        }
+        self.quotes += 1;
+        if quote.last > 0 {
+            self.low = Some( self.low.map_or( quote.last, |low| low.min( quote.last ) ) );
+            self.high = Some( self.high.map_or( quote.last, |high| high.max( quote.last ) ) );
+        }
+        if quote.bid > 0 && quote.ask > 0 {
+            self.spreads += 1;
+            let spread = ( quote.ask - quote.bid ) as f64;
+            let mean = self.avg_spread.unwrap_or( 0.0 );
+            self.avg_spread = Some( mean + ( spread - mean ) / self.spreads as f64 );
+        }
+        // Every quote repeats the last trade until the next one prints
+        if !quote.last_size.is_zero() && !quote.last_trade.is_empty() && quote.last_trade != self.last_trade {
+            self.last_trade = quote.last_trade.clone();
+            self.sizes += 1;
+            let mean = self.typical_size.unwrap_or( 0.0 );
+            self.typical_size = Some( mean + ( quote.last_size.get() as f64 - mean ) / self.sizes as f64 );
+        }
+    }
+}
+
+pub struct SymbolTable {
+    symbols: HashMap<(String, String), SymbolInfo>,
+    clock: Arc<Clock>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable { symbols: HashMap::new(), clock: Arc::new( SystemClock ) }
+    }
+
+    /// Stamps first quotes with `clock` rather than the system clock.
+    pub fn with_clock( mut self, clock: Arc<Clock> ) -> SymbolTable {
+        self.clock = clock;
+        self
+    }
+
+    /// Adds the names from `venue`'s stock listing (cached by the client after the first call).
+    pub fn load_listing( &mut self, client: &StockfighterClient, venue: &str ) -> Result<(), StockfighterErr> {
+        let listing: Vec<Stock> = try!( client.stocks( venue ) );
+        for stock in listing {
+            let info = self.symbols.entry( ( venue.to_owned(), stock.symbol.clone() ) )
+                           .or_insert_with( || SymbolInfo::new( venue, &stock.symbol ) );
+            info.name = Some( stock.name );
+        }
+        Ok( () )
+    }
+
+    /// Folds `quote` into its symbol's statistics.
+    pub fn observe( &mut self, quote: &Quote ) {
+        let now = self.clock.wall();
+        self.symbols.entry( ( quote.venue.clone(), quote.symbol.clone() ) )
+            .or_insert_with( || SymbolInfo::new( &quote.venue, &quote.symbol ) )
+            .observe( quote, now );
+    }
+
+    /// Everything known about `symbol` on `venue`.
+    ///
+    /// # Example
+    /// ```
+    /// let client = market::StockfighterClient::new();
+    /// let mut table = market::symbols::SymbolTable::new();
+    /// table.load_listing( &client, "TESTEX" ).unwrap();
+    /// for _ in 0..100 {
+    ///   table.observe( &client.quote( "TESTEX", "FOOBAR" ).unwrap() );
+    /// }
+    /// let info = table.describe( "TESTEX", "FOOBAR" ).unwrap();
+    /// // Quote a little inside the usual spread, in clips of the usual size
+    /// let width = info.avg_spread.map_or( 20, |spread| ( spread * 0.8 ) as i32 );
+    /// let clip = info.typical_size.map_or( 100, |size| size as u32 );
+    /// println!("{:?}: width {} clip {}", info.name, width, clip );
+    /// ```
+    pub fn describe( &self, venue: &str, symbol: &str ) -> Option<&SymbolInfo> {
+        self.symbols.get( &( venue.to_owned(), symbol.to_owned() ) )
+    }
+
+    /// Every symbol seen, in no particular order.
+    pub fn all( &self ) -> Vec<&SymbolInfo> {
+        self.symbols.values().collect()
+    }
+}
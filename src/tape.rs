@@ -0,0 +1,181 @@
+// Order book history as a stream of changes.
+//
+// Recording a full book snapshot on every poll stores the same few hundred levels over and
+// over when only one or two of them moved. A BookTape keeps a full snapshot (a keyframe) now
+// and then, and between keyframes only the levels that changed, as BookDeltas. It can then
+// rebuild the book as it stood at any recorded timestamp -- "what did the book look like when
+// I sent that order?"
+//
+// Deltas work on aggregate size per price, so a rebuilt book has one entry per price level
+// even where the original listed several orders at that price. Everything the depth and
+// render views show is unaffected.
+
+use std::collections::BTreeMap;
+
+use {Bid, OrderBook, Qty};
+
+/// The levels that changed between two books: (price, new total size), where a size of zero
+/// means the level is gone.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BookDelta {
+    pub ts: String,
+    pub bids: Vec<(i32, Qty)>,
+    pub asks: Vec<(i32, Qty)>,
+}
+
+fn levels( side: &[Bid] ) -> BTreeMap<i32, Qty> {
+    let mut levels = BTreeMap::new();
+    for bid in side {
+        let total = levels.entry( bid.price ).or_insert( Qty( 0 ) );
+        *total = total.saturating_add( bid.qty );
+    }
+    levels
+}
+
+fn changes( before: &[Bid], after: &[Bid] ) -> Vec<(i32, Qty)> {
+    let before = levels( before );
+    let after = levels( after );
+    let mut changed = vec![];
+    for ( &price, &qty ) in &before {
+        match after.get( &price ) {
+            Some( &now ) if now == qty => {},
+            Some( &now ) => changed.push( ( price, now ) ),
+            None => changed.push( ( price, Qty( 0 ) ) ),
+        }
+    }
+    for ( &price, &qty ) in &after {
+        if !before.contains_key( &price ) {
+            changed.push( ( price, qty ) );
+        }
+    }
+    changed.sort_by( |a, b| a.0.cmp( &b.0 ) );
+    changed
+}
+
+fn patch( side: &mut Vec<Bid>, changes: &[(i32, Qty)], is_buy: bool, descending: bool ) {
+    let mut levels = levels( side );
+    for &( price, qty ) in changes {
+        if qty.is_zero() {
+            levels.remove( &price );
+        } else {
+            levels.insert( price, qty );
+        }
+    }
+    let mut patched: Vec<Bid> = levels.into_iter()
+                                      .map( |( price, qty )| Bid { price: price, qty: qty, is_buy: is_buy } )
+                                      .collect();
+    if descending {
+        patched.reverse();
+    }
+    *side = patched;
+}
+
+impl BookDelta {
+    /// What changed going from `before` to `after`, stamped with `after`'s timestamp.
+    pub fn between( before: &OrderBook, after: &OrderBook ) -> BookDelta {
+        BookDelta {
+            ts: after.ts.clone(),
+            bids: changes( &before.bids, &after.bids ),
+            asks: changes( &before.asks, &after.asks ),
+        }
+    }
+
+    pub fn is_empty( &self ) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Applies the changes to `book`, leaving bids best (highest) first and asks best
+    /// (lowest) first, as the API sends them.
+    pub fn apply( &self, book: &mut OrderBook ) {
+        patch( &mut book.bids, &self.bids, true, true );
+        patch( &mut book.asks, &self.asks, false, false );
+        book.ts = self.ts.clone();
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BookTape {
+    keyframe_every: usize,
+    // (index into `deltas` the keyframe comes before, the book at that point)
+    keyframes: Vec<(usize, OrderBook)>,
+    deltas: Vec<BookDelta>,
+    last: Option<OrderBook>,
+}
+
+impl BookTape {
+    /// Stores a full snapshot every `keyframe_every` deltas, so rebuilding a book never
+    /// replays more than that many.
+    ///
+    /// # Example
+    /// ```
+    /// use market::tape::BookTape;
+    /// let client = market::StockfighterClient::new();
+    /// let mut tape = BookTape::new( 100 );
+    /// for _ in 0..1000 {
+    ///   tape.record( &client.order_book( "TESTEX", "FOOBAR" ).unwrap() );
+    /// }
+    /// let when = tape.timestamps()[ 500 ].to_owned();
+    /// let then = tape.at( &when ).unwrap();
+    /// println!("{}", then.render( &[], 5 ) );
+    /// ```
+    pub fn new( keyframe_every: usize ) -> BookTape {
+        BookTape {
+            keyframe_every: keyframe_every.max( 1 ),
+            keyframes: vec![],
+            deltas: vec![],
+            last: None,
+        }
+    }
+
+    /// Adds a snapshot to the tape. Books are expected in timestamp order; one identical to
+    /// the previous isn't stored at all.
+    pub fn record( &mut self, book: &OrderBook ) {
+        let delta = match self.last {
+            Some( ref last ) => BookDelta::between( last, book ),
+            None => {
+                self.keyframes.push( ( 0, book.clone() ) );
+                self.last = Some( book.clone() );
+                return;
+            },
+        };
+        if delta.is_empty() {
+            return;
+        }
+        self.deltas.push( delta );
+        let since_keyframe = self.deltas.len() - self.keyframes.last().map_or( 0, |&( at, _ )| at );
+        if since_keyframe >= self.keyframe_every {
+            self.keyframes.push( ( self.deltas.len(), book.clone() ) );
+        }
+        self.last = Some( book.clone() );
+    }
+
+    /// Timestamps of every stored state, oldest first.
+    pub fn timestamps( &self ) -> Vec<&str> {
+        let first = self.keyframes.first().map( |&( _, ref book )| book.ts.as_str() );
+        first.into_iter().chain( self.deltas.iter().map( |delta| delta.ts.as_str() ) ).collect()
+    }
+
+    /// The book as it stood at `ts` (an ISO-8601 timestamp as the API reports them): the last
+    /// recorded state at or before it. None if `ts` is before the first snapshot.
+    pub fn at( &self, ts: &str ) -> Option<OrderBook> {
+        // How many deltas are at or before `ts`
+        let applied = self.deltas.iter().take_while( |delta| delta.ts.as_str() <= ts ).count();
+        let &( start, ref keyframe ) = match self.keyframes.iter().rev().find( |&&( at, _ )| at <= applied ) {
+            Some( keyframe ) => keyframe,
+            None => return None,
+        };
+        if start == 0 && keyframe.ts.as_str() > ts {
+            return None;
+        }
+        let mut book = keyframe.clone();
+        for delta in &self.deltas[ start..applied ] {
+            delta.apply( &mut book );
+        }
+        Some( book )
+    }
+
+    /// How many keyframes and deltas are stored.
+    pub fn len( &self ) -> ( usize, usize ) {
+        ( self.keyframes.len(), self.deltas.len() )
+    }
+}
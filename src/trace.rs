@@ -0,0 +1,213 @@
+// Spans and events for looking at where a live bot spends its time (feature "tracing").
+//
+// Every HTTP call runs inside an "http" span, and every order submission inside an "order" span
+// holding "submit", "ack" and one "fill" event per fill in the response. Status and cancel
+// calls emit "fill" events too, so an order's whole submit -> ack -> fill life can be put back
+// together from its id. (There's no websocket layer yet; its reads belong in spans too once
+// there is.)
+//
+// Nothing is recorded until a Subscriber is installed with set_subscriber. Spans report when
+// they close, with their parent's id, so nesting survives; Collector keeps everything in memory
+// and can fold it into the "a;b;c micros" lines flamegraph tools read. Without the feature the
+// library's trace_span! / trace_event! calls compile away to nothing.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::time::{Duration, Instant};
+
+/// A span that has closed.
+#[derive(Debug, Clone)]
+pub struct SpanRecord {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub name: &'static str,
+    pub fields: Vec<( &'static str, String )>,
+    pub elapsed: Duration,
+}
+
+/// Something that happened inside a span (or outside any, with `span` None).
+#[derive(Debug, Clone)]
+pub struct EventRecord {
+    pub span: Option<usize>,
+    pub name: &'static str,
+    pub fields: Vec<( &'static str, String )>,
+    pub at: Instant,
+}
+
+/// Where spans and events go. Called on whichever thread produced them.
+pub trait Subscriber: Send + Sync {
+    fn span( &self, span: &SpanRecord );
+    fn event( &self, event: &EventRecord );
+}
+
+// A leaked Box<Arc<Subscriber>>, or 0 before one is installed
+static SUBSCRIBER: AtomicUsize = ATOMIC_USIZE_INIT;
+static NEXT_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+thread_local!( static CURRENT: Cell<usize> = Cell::new( 0 ) );
+
+/// Installs the process-wide subscriber. Only the first call wins; later ones get their
+/// subscriber handed back.
+pub fn set_subscriber( subscriber: Arc<Subscriber> ) -> Result<(), Arc<Subscriber>> {
+    let boxed = Box::into_raw( Box::new( subscriber ) );
+    if SUBSCRIBER.compare_and_swap( 0, boxed as usize, Ordering::SeqCst ) == 0 {
+        Ok( () )
+    } else {
+        // Never shared, so it's still ours to take back
+        Err( *unsafe { Box::from_raw( boxed ) } )
+    }
+}
+
+fn subscriber() -> Option<&'static Arc<Subscriber>> {
+    let raw = SUBSCRIBER.load( Ordering::SeqCst );
+    if raw == 0 {
+        None
+    } else {
+        // Installed once and never freed
+        Some( unsafe { &*( raw as *const Arc<Subscriber> ) } )
+    }
+}
+
+/// An open span. It becomes the current span on this thread until it's dropped, at which
+/// point it's reported with how long it was open.
+pub struct Span {
+    id: usize,
+    parent: Option<usize>,
+    name: &'static str,
+    fields: Vec<( &'static str, String )>,
+    started: Instant,
+}
+
+impl Span {
+    pub fn enter( name: &'static str, fields: Vec<( &'static str, String )> ) -> Span {
+        let id = NEXT_ID.fetch_add( 1, Ordering::SeqCst ) + 1;
+        let parent = CURRENT.with( |current| {
+            let parent = current.get();
+            current.set( id );
+            parent
+        });
+        Span {
+            id: id,
+            parent: if parent == 0 { None } else { Some( parent ) },
+            name: name,
+            fields: fields,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn id( &self ) -> usize {
+        self.id
+    }
+}
+
+impl Drop for Span {
+    fn drop( &mut self ) {
+        CURRENT.with( |current| current.set( self.parent.unwrap_or( 0 ) ) );
+        if let Some( subscriber ) = subscriber() {
+            subscriber.span( &SpanRecord {
+                id: self.id,
+                parent: self.parent,
+                name: self.name,
+                fields: ::std::mem::replace( &mut self.fields, vec![] ),
+                elapsed: self.started.elapsed(),
+            });
+        }
+    }
+}
+
+/// Reports an event inside the current span.
+pub fn event( name: &'static str, fields: Vec<( &'static str, String )> ) {
+    if let Some( subscriber ) = subscriber() {
+        let current = CURRENT.with( |current| current.get() );
+        subscriber.event( &EventRecord {
+            span: if current == 0 { None } else { Some( current ) },
+            name: name,
+            fields: fields,
+            at: Instant::now(),
+        });
+    }
+}
+
+/// Keeps every span and event in memory.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use market::trace::{self, Collector, Span};
+/// let collector = Arc::new( Collector::new() );
+/// trace::set_subscriber( collector.clone() ).ok().expect( "first subscriber" );
+/// {
+///   let _order = Span::enter( "order", vec![ ( "venue", "TESTEX".to_owned() ) ] );
+///   trace::event( "submit", vec![] );
+///   let _http = Span::enter( "http", vec![] );
+/// }
+/// assert_eq!( collector.spans().len(), 2 );
+/// assert_eq!( collector.events()[0].name, "submit" );
+/// assert!( collector.folded().contains( "order;http " ) );
+/// ```
+#[derive(Debug)]
+pub struct Collector {
+    spans: Mutex<Vec<SpanRecord>>,
+    events: Mutex<Vec<EventRecord>>,
+}
+
+impl Collector {
+    pub fn new() -> Collector {
+        Collector { spans: Mutex::new( vec![] ), events: Mutex::new( vec![] ) }
+    }
+
+    /// Closed spans, in the order they closed (children before their parents).
+    pub fn spans( &self ) -> Vec<SpanRecord> {
+        self.spans.lock().unwrap_or_else( |poisoned| poisoned.into_inner() ).clone()
+    }
+
+    pub fn events( &self ) -> Vec<EventRecord> {
+        self.events.lock().unwrap_or_else( |poisoned| poisoned.into_inner() ).clone()
+    }
+
+    /// One "root;child;grandchild micros" line per closed span, with each span's own time
+    /// (its children's taken out) -- the folded-stack format flamegraph tools take.
+    pub fn folded( &self ) -> String {
+        let spans = self.spans();
+        let by_id: HashMap<usize, &SpanRecord> = spans.iter().map( |span| ( span.id, span ) ).collect();
+        let mut children: HashMap<usize, Duration> = HashMap::new();
+        for span in &spans {
+            if let Some( parent ) = span.parent {
+                let total = children.entry( parent ).or_insert( Duration::from_secs( 0 ) );
+                *total = *total + span.elapsed;
+            }
+        }
+        let mut out = String::new();
+        for span in &spans {
+            let mut path = vec![ span.name ];
+            let mut parent = span.parent;
+            while let Some( id ) = parent {
+                match by_id.get( &id ) {
+                    Some( up ) => {
+                        path.push( up.name );
+                        parent = up.parent;
+                    },
+                    None => break,
+                }
+            }
+            path.reverse();
+            let inside = children.get( &span.id ).cloned().unwrap_or( Duration::from_secs( 0 ) );
+            let own = if span.elapsed > inside { span.elapsed - inside } else { Duration::from_secs( 0 ) };
+            let micros = own.as_secs() * 1_000_000 + ( own.subsec_nanos() / 1000 ) as u64;
+            out.push_str( &format!("{} {}\n", path.join( ";" ), micros ) );
+        }
+        out
+    }
+}
+
+impl Subscriber for Collector {
+    fn span( &self, span: &SpanRecord ) {
+        self.spans.lock().unwrap_or_else( |poisoned| poisoned.into_inner() ).push( span.clone() );
+    }
+
+    fn event( &self, event: &EventRecord ) {
+        self.events.lock().unwrap_or_else( |poisoned| poisoned.into_inner() ).push( event.clone() );
+    }
+}
@@ -0,0 +1,77 @@
+// Waiting for an order to show up.
+//
+// The order ack comes back before every part of the venue has caught up with it: poll the
+// account's order list straight after placing an order and it may not be there yet, so logic
+// that reconciles "what I think I have resting" against the server wrongly concludes the order
+// was lost and sends it again. `wait_visible` holds off (for a bounded time) until the order
+// is listed for the account, or has started filling, before anyone acts on the new state.
+
+use hyper::Client;
+use hyper::header::Headers;
+
+use std::io::Read;
+use std::time::Duration;
+
+use {Auth, OrderResponse, Route, StockfighterErr};
+use decode;
+use poll::Poller;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountOrders {
+    pub ok: bool,
+    #[serde(default)]
+    pub venue: String,
+    #[serde(default)]
+    pub orders: Vec<OrderResponse>,
+}
+
+/// Every order `account` has on `venue`, open or closed.
+pub fn account_orders( venue: &str, account: &str, auth: &Auth ) -> Result<Vec<OrderResponse>, StockfighterErr> {
+    let url = try!( Route::AccountOrders { venue: venue, account: account }.url() );
+    let mut headers = Headers::new();
+    try!( auth.apply( &mut headers ) );
+    let client = Client::new();
+    let mut response = try!( client.get( &url ).headers( headers ).send() );
+    let mut body = String::new();
+    try!( response.read_to_string( &mut body ) );
+    let listing: AccountOrders = try!( decode::checked( &body ) );
+    Ok( listing.orders )
+}
+
+impl OrderResponse {
+    /// Polls every `interval` until the order appears in its account's order list, or has
+    /// fills, and returns its state from there. Gives up with `TimedOut` after `deadline`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// let order = market::Order::new( "EXB123456".to_owned(), "TESTEX".to_owned(),
+    ///                                 "FOOBAR".to_owned(), 5000, market::Qty( 100 ),
+    ///                                 "buy".to_owned(), "limit".to_owned() );
+    /// let ack = order.process_order().unwrap();
+    /// let seen = ack.wait_visible( Duration::from_millis( 50 ), Duration::from_secs( 2 ) ).unwrap();
+    /// println!("order {} is live, {} filled", seen.id, seen.total_filled );
+    /// ```
+    pub fn wait_visible( &self, interval: Duration, deadline: Duration ) -> Result<OrderResponse, StockfighterErr> {
+        self.wait_visible_with( interval, deadline, &Auth::Env )
+    }
+
+    /// Same as wait_visible, authenticating with `auth`.
+    pub fn wait_visible_with( &self, interval: Duration, deadline: Duration, auth: &Auth )
+                              -> Result<OrderResponse, StockfighterErr> {
+        if !self.fills.is_empty() {
+            return Ok( self.clone() );
+        }
+        let found = try!( Poller::new( interval ).deadline( deadline ).run(
+            || {
+                let orders = try!( account_orders( &self.venue, &self.account, auth ) );
+                Ok( orders.into_iter().find( |order| order.id == self.id ) )
+            },
+            |listed| listed.is_some() ) );
+        match found {
+            Some( order ) => Ok( order ),
+            // The predicate only accepts Some
+            None => Err( StockfighterErr::TimedOut ),
+        }
+    }
+}